@@ -0,0 +1,51 @@
+// compares the interpreter with and without the decoded instruction cache and the recompiler
+// run with: cargo bench --no-default-features --bench execution
+use std::time::{Duration, Instant};
+
+use chip8::{Chip8, Engine, Quirks, RomLoader};
+
+const ROMS: [&str; 3] = [
+  "roms/demos/Trip8 Demo (2008) [Revival Studios].ch8",
+  "roms/demos/Sierpinski [Sergey Naydenov, 2010].ch8",
+  "roms/BC_test.ch8",
+];
+const FRAMES: usize = 600;
+const INSTRUCTIONS_PER_FRAME: usize = 1000;
+const RUNS: usize = 5;
+
+fn run(rom: &[u8], engine: Engine, instruction_cache: bool) -> Duration {
+  let mut best = Duration::from_secs(u64::MAX);
+
+  for _ in 0..RUNS {
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.set_seed(1);
+    chip8.set_engine(engine);
+    chip8.set_instruction_cache(instruction_cache);
+    chip8.load(rom.to_vec()).unwrap();
+
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+      if chip8.run_frame(INSTRUCTIONS_PER_FRAME).is_err() {
+        break;
+      }
+    }
+    best = best.min(start.elapsed());
+  }
+
+  best
+}
+
+fn main() {
+  for path in ROMS.iter() {
+    let rom = RomLoader::load(path).unwrap().rom;
+
+    let uncached = run(&rom, Engine::Interpreter, false);
+    let cached = run(&rom, Engine::Interpreter, true);
+    let recompiled = run(&rom, Engine::Recompiler, true);
+
+    println!("{}", path);
+    println!("  decode every fetch: {:>10.3?}", uncached);
+    println!("  instruction cache:  {:>10.3?} ({:.2}x)", cached, uncached.as_secs_f64() / cached.as_secs_f64());
+    println!("  recompiler:         {:>10.3?} ({:.2}x)", recompiled, uncached.as_secs_f64() / recompiled.as_secs_f64());
+  }
+}
@@ -0,0 +1,182 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+pub const EXECUTED: u8 = 0b001;
+pub const READ: u8 = 0b010;
+pub const WRITTEN: u8 = 0b100;
+
+const OVERLAY_COLUMNS: usize = 64; //bytes per row of the overlay image
+const OVERLAY_CELL: usize = 8; //pixels per byte
+
+// how every memory address was accessed, executed bytes are all bytes of each executed instruction
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Coverage {
+  flags: Vec<u8>,
+}
+
+impl Coverage {
+  pub fn new(size: usize) -> Coverage {
+    Coverage { flags: vec![0; size] }
+  }
+
+  pub fn resize(&mut self, size: usize) {
+    self.flags.resize(size, 0);
+  }
+
+  pub fn clear(&mut self) {
+    for flags in self.flags.iter_mut() {
+      *flags = 0;
+    }
+  }
+
+  pub fn mark(&mut self, address: usize, flag: u8) {
+    if let Some(flags) = self.flags.get_mut(address) {
+      *flags |= flag;
+    }
+  }
+
+  pub fn flags(&self, address: usize) -> u8 {
+    self.flags.get(address).copied().unwrap_or(0)
+  }
+
+  pub fn as_slice(&self) -> &[u8] {
+    &self.flags
+  }
+
+  // bytes in range with the flag set
+  pub fn count(&self, range: Range<usize>, flag: u8) -> usize {
+    range.filter(|address| self.flags(*address) & flag != 0).count()
+  }
+
+  // a summary followed by one line per run of equally accessed addresses, untouched runs are left out
+  //   0200-0213 X--
+  pub fn to_text(&self, range: Range<usize>) -> String {
+    let mut text = String::new();
+    let size = range.len().max(1);
+
+    writeln!(text, "# coverage of {:04X}-{:04X}, X executed, R read, W written", range.start, range.end.saturating_sub(1)).unwrap();
+    for (name, flag) in [("executed", EXECUTED), ("read", READ), ("written", WRITTEN)].iter() {
+      let count = self.count(range.clone(), *flag);
+      writeln!(text, "# {}: {} bytes ({:.1}%)", name, count, count as f64 * 100.0 / size as f64).unwrap();
+    }
+
+    let mut address = range.start;
+    while address < range.end {
+      let flags = self.flags(address);
+      let start = address;
+      while address < range.end && self.flags(address) == flags {
+        address += 1;
+      }
+
+      if flags != 0 {
+        writeln!(text, "{:04X}-{:04X} {}", start, address - 1, flag_letters(flags)).unwrap();
+      }
+    }
+
+    text
+  }
+
+  // binary ppm image with one cell per byte, 64 bytes per row
+  // executed is green, read blue and written red, bytes accessed in several ways mix the colours
+  pub fn to_overlay(&self, range: Range<usize>) -> Vec<u8> {
+    let rows = range.len().div_ceil(OVERLAY_COLUMNS);
+    let width = OVERLAY_COLUMNS * OVERLAY_CELL;
+    let height = rows.max(1) * OVERLAY_CELL;
+
+    let mut image = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for y in 0..height {
+      for x in 0..width {
+        let offset = y / OVERLAY_CELL * OVERLAY_COLUMNS + x / OVERLAY_CELL;
+        let address = range.start + offset;
+
+        let color = if offset >= range.len() {
+          [0, 0, 0]
+        } else {
+          flag_color(self.flags(address))
+        };
+        image.extend_from_slice(&color);
+      }
+    }
+
+    image
+  }
+}
+
+fn flag_letters(flags: u8) -> String {
+  [(EXECUTED, 'X'), (READ, 'R'), (WRITTEN, 'W')].iter()
+    .map(|(flag, letter)| if flags & flag != 0 { *letter } else { '-' })
+    .collect()
+}
+
+fn flag_color(flags: u8) -> [u8; 3] {
+  if flags == 0 {
+    return [40, 40, 40];
+  }
+
+  [
+    if flags & WRITTEN != 0 { 230 } else { 0 },
+    if flags & EXECUTED != 0 { 210 } else { 0 },
+    if flags & READ != 0 { 255 } else { 0 },
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn flags_accumulate_per_address() {
+    let mut coverage = Coverage::new(16);
+    coverage.mark(2, EXECUTED);
+    coverage.mark(2, READ);
+    coverage.mark(3, WRITTEN);
+    coverage.mark(16, READ); //outside, ignored
+
+    assert_eq!(coverage.flags(2), EXECUTED | READ);
+    assert_eq!(coverage.flags(3), WRITTEN);
+    assert_eq!(coverage.flags(16), 0);
+    assert_eq!(coverage.count(0..16, READ), 1);
+
+    coverage.clear();
+    assert_eq!(coverage.count(0..16, EXECUTED | READ | WRITTEN), 0);
+  }
+
+  #[test]
+  fn text_lists_runs_of_equal_access() {
+    let mut coverage = Coverage::new(0x20);
+    for address in 0x10..0x14 {
+      coverage.mark(address, EXECUTED);
+    }
+    coverage.mark(0x18, READ);
+    coverage.mark(0x19, READ | WRITTEN);
+
+    assert_eq!(coverage.to_text(0x10..0x1A), "\
+# coverage of 0010-0019, X executed, R read, W written
+# executed: 4 bytes (40.0%)
+# read: 2 bytes (20.0%)
+# written: 1 bytes (10.0%)
+0010-0013 X--
+0018-0018 -R-
+0019-0019 -RW
+");
+  }
+
+  #[test]
+  fn overlay_has_one_cell_per_byte() {
+    let mut coverage = Coverage::new(0x100);
+    coverage.mark(0x40, EXECUTED);
+
+    let overlay = coverage.to_overlay(0x00..0x41);
+    let header = b"P6\n512 16\n255\n";
+    assert_eq!(&overlay[..header.len()], header);
+    assert_eq!(overlay.len(), header.len() + 512 * 16 * 3);
+
+    let pixel = |x: usize, y: usize| {
+      let offset = header.len() + (y * 512 + x) * 3;
+      [overlay[offset], overlay[offset + 1], overlay[offset + 2]]
+    };
+    assert_eq!(pixel(0, 0), [40, 40, 40]);
+    assert_eq!(pixel(7, 15), [0, 210, 0]); //0x40 starts the second row
+    assert_eq!(pixel(8, 15), [0, 0, 0]); //behind the range
+  }
+}
@@ -1,300 +1,1202 @@
-use std::time::{Duration, Instant};
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::error::Chip8Error;
+use crate::font::{Font, BIG_GLYPH_SIZE, SMALL_FONT_SIZE, SMALL_GLYPH_SIZE};
+use crate::instruction::{Instruction, UnknownOpcode};
+use crate::keypad::{Keypad, KEY_COUNT};
+use crate::memory::Memory;
+use crate::model::MachineModel;
+use crate::observer::{DrawEvent, FrameEvent, InstructionEvent, Observer, ObserverId, Registers, Timer};
+use crate::quirks::Quirks;
+use crate::random::{RandomSource, XorShiftRandom};
+use crate::recompiler::{self, Block, BlockCache, CompiledInstruction, Operation};
+use crate::screen::{Screen, ALL_PLANES, PLANE_COUNT};
+use crate::state::{self, StateError, StateReader, StateWriter};
+use crate::timing::{self, Timing, VIP_AVAILABLE_CYCLES};
 
 const INSTRUCTION_SIZE: usize = 2;
-const MEMORY_START: usize = 0x0200;
-const MEMORY_SIZE: usize = 0x0FFF; //4K
+pub const DEFAULT_PROGRAM_START: usize = 0x0200;
+const MEMORY_SIZE: usize = 0x1000; //4K
+const XOCHIP_MEMORY_SIZE: usize = 0x10000; //64K
+const LONG_I_OPCODE: u16 = 0xF000;
+
+//two-page hires roms start with a jump over the patched interpreter to the actual program
+const HIRES_START_OPCODE: u16 = 0x1260;
+const HIRES_PROGRAM_OPCODE: u16 = 0x12C0;
+const HIRES_CLEAR_OPCODE: u16 = 0x0230;
+const NO_KEY: u8 = 0xFF; //save state marker for no key pressed during fx0a
+pub const DEFAULT_STACK_DEPTH: usize = 16;
+const VIP_STACK_START: usize = 0x0EA0; //where the cosmac vip interpreter keeps its stack
+pub const DEFAULT_FONT_ADDRESS: usize = 0x0000;
+
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 8;
+pub const DEFAULT_TIMER_FREQUENCY: u32 = 60; //Hz
+const MAX_CATCH_UP_FRAMES: u32 = 5; //drop frames if the frontend falls further behind
 
 pub const CHIP8_WIDTH: usize = 64;
 pub const CHIP8_HEIGHT: usize = 32;
+pub const SCHIP_WIDTH: usize = 128;
+pub const SCHIP_HEIGHT: usize = 64;
+pub const HIRES_CHIP8_HEIGHT: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InstructionSet {
+  Chip8,
+  HiresChip8, //cosmac vip two-page 64x64 mode
+  SuperChip10, //without the scroll instructions of 1.1
+  SuperChip,
+  XoChip,
+}
+
+impl InstructionSet {
+  pub fn memory_size(&self) -> usize {
+    match self {
+      InstructionSet::XoChip => XOCHIP_MEMORY_SIZE,
+      _ => MEMORY_SIZE
+    }
+  }
+
+  pub fn screen_size(&self) -> (usize, usize) {
+    match self {
+      InstructionSet::HiresChip8 => (CHIP8_WIDTH, HIRES_CHIP8_HEIGHT),
+      _ => (CHIP8_WIDTH, CHIP8_HEIGHT)
+    }
+  }
+
+  fn to_byte(self) -> u8 {
+    match self {
+      InstructionSet::Chip8 => 0,
+      InstructionSet::HiresChip8 => 1,
+      InstructionSet::SuperChip => 2,
+      InstructionSet::XoChip => 3,
+      InstructionSet::SuperChip10 => 4,
+    }
+  }
 
-enum ProgramCounterAction {
+  fn from_byte(byte: u8) -> Option<InstructionSet> {
+    match byte {
+      0 => Some(InstructionSet::Chip8),
+      1 => Some(InstructionSet::HiresChip8),
+      2 => Some(InstructionSet::SuperChip),
+      3 => Some(InstructionSet::XoChip),
+      4 => Some(InstructionSet::SuperChip10),
+      _ => None
+    }
+  }
+
+  fn extends_superchip(&self) -> bool {
+    matches!(self, InstructionSet::SuperChip10 | InstructionSet::SuperChip | InstructionSet::XoChip)
+  }
+
+  fn supports(&self, instruction: &Instruction) -> bool {
+    match self {
+      InstructionSet::Chip8 | InstructionSet::HiresChip8 => !instruction.is_superchip() && !instruction.is_xochip(),
+      InstructionSet::SuperChip10 => !instruction.is_superchip11() && !instruction.is_xochip(),
+      InstructionSet::SuperChip => !instruction.is_xochip(),
+      InstructionSet::XoChip => true,
+    }
+  }
+}
+
+// how instructions are executed, both give the same results
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+  Interpreter, //decode and execute one instruction at a time
+  Recompiler, //translate basic blocks into chains of closures and run those
+}
+
+pub(crate) enum ProgramCounterAction {
   Increment,
   Skip,
   Jump(usize),
 }
 
 pub struct TickResult<'a> {
-  pub screen_buffer: &'a [[bool; CHIP8_WIDTH]; CHIP8_HEIGHT],
+  pub screen_buffer: &'a Screen,
   pub screen_changed: bool,
   pub play_sound: bool,
+  pub exited: bool,
+  pub waiting_for_key: bool,
 }
 
 pub struct Chip8 {
-  screen_buffer: [[bool; CHIP8_WIDTH]; CHIP8_HEIGHT],
-  //screen is 64x32
+  quirks: Quirks,
+  instruction_set: InstructionSet,
+
+  screen_buffer: Screen,
+  //screen is 64x32, 128x64 in super-chip hires mode
   screen_changed: bool,
+  planes: u8, //xo-chip bitplanes selected for drawing
+  exited: bool, //super-chip exit instruction was executed
 
-  input: [bool; 16],
+  keypad: Keypad,
   wait_for_input: bool,
   //wait for the next keypress
   input_register: usize, // where to put the input when we wait for it
-
-  last_tick: Instant,
-  last_timer_tick: Instant,
-
-  memory: [u8; MEMORY_SIZE],
-  //memory is 4k
+  input_key: Option<u8>, //key pressed while waiting, stored once it is released
+  wait_for_display: bool, //wait for the next timer tick after drawing
+
+  instructions_per_frame: usize,
+  instruction_cache: bool, //reuse decoded instructions instead of decoding every fetch
+  engine: Engine,
+  blocks: BlockCache,
+  timing: Timing,
+  cycle_carry: u32, //vip machine cycles the last instruction ran into the current frame
+  timer_frequency: u32,
+  frame_time: Duration, //time accumulated towards the next frame
+  frame_count: u64,
+
+  memory: Memory,
+  //memory is 4k, 64k for xo-chip
   v: [u8; 16],  //16 8bit registers
   i: u16,  //one 16bit special register
   delay_timer: u8,  //delay counts down to zero
   sound_timer: u8,  //sound counts down to zero and plays sound
   program_counter: usize,
+  program_start: usize, //where roms are loaded and execution starts
+  rom_size: usize,
   //program counter
   stack: Vec<usize>,     //stack
+  stack_depth: usize,
+  stack_in_memory: bool, //mirror the stack to VIP_STACK_START like the cosmac vip
+  flags: [u8; 16], //super-chip rpl user flags
+  font: Font,
+  font_address: usize, //small glyphs start here, the big ones follow right after
+
+  random: Box<dyn RandomSource>,
+  rom_hash: u64, //identifies the rom save states belong to
+
+  observers: Vec<(ObserverId, Box<dyn Observer>)>,
+  next_observer: usize,
+  sound_playing: bool, //last sound state reported to the observers
+}
+
+impl Default for Chip8 {
+  fn default() -> Self {
+    Self::new(Quirks::default())
+  }
 }
 
 impl Chip8 {
-  pub fn new() -> Chip8 {
+  pub fn new(quirks: Quirks) -> Chip8 {
     Chip8 {
-      screen_buffer: [[false; CHIP8_WIDTH]; CHIP8_HEIGHT],
+      quirks,
+      instruction_set: InstructionSet::Chip8,
+
+      screen_buffer: Screen::new(CHIP8_WIDTH, CHIP8_HEIGHT),
       screen_changed: false,
+      planes: 1,
+      exited: false,
 
       wait_for_input: false,
-      input: [false; 16],
+      keypad: Keypad::new(),
       input_register: 0,
-
-      last_tick: Instant::now(),
-      last_timer_tick: Instant::now(),
-
-      memory: [0; MEMORY_SIZE],
+      input_key: None,
+      wait_for_display: false,
+
+      instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+      instruction_cache: true,
+      engine: Engine::Interpreter,
+      blocks: BlockCache::new(),
+      timing: Timing::Simple,
+      cycle_carry: 0,
+      timer_frequency: DEFAULT_TIMER_FREQUENCY,
+      frame_time: Duration::from_secs(0),
+      frame_count: 0,
+
+      memory: Memory::new(MEMORY_SIZE),
       v: [0; 16],
       i: 0,
       delay_timer: 0,
       sound_timer: 0,
-      program_counter: MEMORY_START,
+      program_counter: DEFAULT_PROGRAM_START,
+      program_start: DEFAULT_PROGRAM_START,
+      rom_size: 0,
       stack: Vec::new(),
+      stack_depth: DEFAULT_STACK_DEPTH,
+      stack_in_memory: false,
+      flags: [0; 16],
+      font: Font::default(),
+      font_address: DEFAULT_FONT_ADDRESS,
+
+      random: Box::new(XorShiftRandom::from_entropy()),
+      rom_hash: state::rom_hash(&[]),
+
+      observers: Vec::new(),
+      next_observer: 0,
+      sound_playing: false,
+    }
+  }
+
+  // a machine configured like the given model, the rom still has to be loaded
+  pub fn with_model(model: MachineModel) -> Result<Chip8, Chip8Error> {
+    let mut chip8 = Chip8::new(model.quirks());
+    chip8.set_model(model)?;
+    Ok(chip8)
+  }
+
+  pub fn set_model(&mut self, model: MachineModel) -> Result<(), Chip8Error> {
+    self.quirks = model.quirks();
+    self.set_instruction_set(model.instruction_set());
+    self.memory.resize(model.memory_size());
+    self.set_program_start(model.program_start());
+    self.stack_depth = model.stack_depth();
+    self.stack_in_memory = model.stack_in_memory();
+    self.set_timing(model.timing());
+    self.set_instructions_per_frame(model.instructions_per_frame());
+    self.set_font(Font::builtin(model.font_set()))
+  }
+
+  pub fn quirks(&self) -> Quirks {
+    self.quirks
+  }
+
+  pub fn set_quirks(&mut self, quirks: Quirks) {
+    self.quirks = quirks;
+  }
+
+  pub fn program_start(&self) -> usize {
+    self.program_start
+  }
+
+  // also moves the program counter there, so set it before running
+  pub fn set_program_start(&mut self, program_start: usize) {
+    self.program_start = program_start;
+    self.program_counter = program_start;
+  }
+
+  // the addresses the loaded rom occupies
+  pub fn rom_range(&self) -> Range<usize> {
+    self.program_start..self.program_start + self.rom_size
+  }
+
+  pub fn load(&mut self, rom: Vec<u8>) -> Result<(), Chip8Error> {
+    if self.program_start + rom.len() > self.memory.len() {
+      return Err(Chip8Error::RomTooLarge { size: rom.len() });
+    }
+
+    self.rom_hash = state::rom_hash(&rom);
+
+    if self.instruction_set == InstructionSet::Chip8 && rom.starts_with(&HIRES_START_OPCODE.to_be_bytes()) {
+      self.set_instruction_set(InstructionSet::HiresChip8);
+    }
+
+    let font = self.font.clone();
+    self.install_font(&font, self.font_address)?;
+    self.memory.copy_from(self.program_start, &rom)?;
+    self.rom_size = rom.len();
+
+    Ok(())
+  }
+
+  pub fn instruction_set(&self) -> InstructionSet {
+    self.instruction_set
+  }
+
+  pub fn set_instruction_set(&mut self, instruction_set: InstructionSet) {
+    self.instruction_set = instruction_set;
+    self.memory.resize(instruction_set.memory_size());
+
+    let (width, height) = instruction_set.screen_size();
+    self.screen_buffer.resize(width, height);
+  }
+
+  pub fn font(&self) -> &Font {
+    &self.font
+  }
+
+  pub fn set_font(&mut self, font: Font) -> Result<(), Chip8Error> {
+    self.install_font(&font, self.font_address)?;
+    self.font = font;
+    Ok(())
+  }
+
+  pub fn font_address(&self) -> usize {
+    self.font_address
+  }
+
+  pub fn set_font_address(&mut self, font_address: usize) -> Result<(), Chip8Error> {
+    let font = self.font.clone();
+    self.install_font(&font, font_address)?;
+    self.font_address = font_address;
+    Ok(())
+  }
+
+  // memory is only touched if the whole font fits
+  fn install_font(&mut self, font: &Font, font_address: usize) -> Result<(), Chip8Error> {
+    let end = font_address + SMALL_FONT_SIZE + font.big().len();
+    if end > self.memory.len() {
+      return Err(Chip8Error::MemoryOutOfRange { address: end });
+    }
+
+    self.memory.copy_from(font_address, font.small())?;
+    self.memory.copy_from(font_address + SMALL_FONT_SIZE, font.big())
+  }
+
+  pub fn set_random_source(&mut self, random: Box<dyn RandomSource>) {
+    self.random = random;
+  }
+
+  pub fn random_source(&self) -> &dyn RandomSource {
+    self.random.as_ref()
+  }
+
+  pub fn seed(&self) -> u64 {
+    self.random.seed()
+  }
+
+  pub fn set_seed(&mut self, seed: u64) {
+    self.random.reseed(seed);
+  }
+
+  pub fn stack_depth(&self) -> usize {
+    self.stack_depth
+  }
+
+  pub fn set_stack_depth(&mut self, stack_depth: usize) {
+    self.stack_depth = stack_depth;
+  }
+
+  pub fn stack_in_memory(&self) -> bool {
+    self.stack_in_memory
+  }
+
+  pub fn set_stack_in_memory(&mut self, stack_in_memory: bool) {
+    self.stack_in_memory = stack_in_memory;
+  }
+
+  pub fn add_observer(&mut self, observer: Box<dyn Observer>) -> ObserverId {
+    let id = ObserverId(self.next_observer);
+    self.next_observer += 1;
+    self.observers.push((id, observer));
+    id
+  }
+
+  pub fn remove_observer(&mut self, id: ObserverId) -> Option<Box<dyn Observer>> {
+    let index = self.observers.iter().position(|(observer_id, _)| *observer_id == id)?;
+    Some(self.observers.remove(index).1)
+  }
+
+  fn notify(&mut self, mut callback: impl FnMut(&mut dyn Observer)) {
+    for (_, observer) in self.observers.iter_mut() {
+      callback(observer.as_mut());
+    }
+  }
+
+  // reports the sound starting or stopping whenever the sound timer crosses zero
+  fn update_sound(&mut self) {
+    let playing = self.sound_timer > 0;
+    if playing != self.sound_playing {
+      self.sound_playing = playing;
+      self.notify(|observer| observer.sound(playing));
+    }
+  }
+
+  pub fn registers(&self) -> Registers {
+    Registers {
+      v: self.v,
+      i: self.i,
+      delay_timer: self.delay_timer,
+      sound_timer: self.sound_timer,
+      program_counter: self.program_counter,
+      stack_pointer: self.stack.len(),
     }
   }
 
-  pub fn load(&mut self, rom: Vec<u8>) {
-    for (i, line) in FONT_SET.iter().enumerate() {
-      for (j, byte) in line.iter().enumerate() {
-        self.memory[i * 5 + j] = *byte;
+  pub fn has_exited(&self) -> bool {
+    self.exited
+  }
+
+  pub fn rom_hash(&self) -> u64 {
+    self.rom_hash
+  }
+
+  pub fn save_state(&self) -> Vec<u8> {
+    let mut writer = StateWriter::new(self.rom_hash);
+
+    let mut cpu = Vec::new();
+    cpu.push(self.instruction_set.to_byte());
+    cpu.extend_from_slice(&self.v);
+    cpu.extend_from_slice(&self.i.to_le_bytes());
+    cpu.extend_from_slice(&(self.program_counter as u32).to_le_bytes());
+    cpu.push(self.delay_timer);
+    cpu.push(self.sound_timer);
+    cpu.push(self.wait_for_input as u8);
+    cpu.push(self.input_register as u8);
+    cpu.push(self.wait_for_display as u8);
+    cpu.push(self.exited as u8);
+    cpu.push(self.planes);
+    writer.section(b"CPU ", &cpu);
+
+    writer.section(b"MEM ", self.memory.as_slice());
+
+    let mut stack = Vec::new();
+    for address in self.stack.iter() {
+      stack.extend_from_slice(&(*address as u32).to_le_bytes());
+    }
+    writer.section(b"STCK", &stack);
+
+    let mut screen = Vec::new();
+    screen.extend_from_slice(&(self.screen_buffer.width() as u16).to_le_bytes());
+    screen.extend_from_slice(&(self.screen_buffer.height() as u16).to_le_bytes());
+    for row in self.screen_buffer.rows() {
+      screen.extend_from_slice(row);
+    }
+    writer.section(b"SCRN", &screen);
+
+    writer.section(b"FLAG", &self.flags);
+
+    let mut keys = Vec::new();
+    for bits in self.keypad.to_bits().iter() {
+      keys.extend_from_slice(&bits.to_le_bytes());
+    }
+    keys.push(self.input_key.unwrap_or(NO_KEY));
+    writer.section(b"KEYS", &keys);
+
+    let mut random = Vec::new();
+    random.extend_from_slice(&self.random.seed().to_le_bytes());
+    random.extend_from_slice(&self.random.state().to_le_bytes());
+    writer.section(b"RAND", &random);
+
+    writer.finish()
+  }
+
+  // the machine is only modified once the whole state has been read successfully
+  pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+    let reader = StateReader::new(data, self.rom_hash)?;
+
+    let mut cpu = reader.section(b"CPU ")?;
+    let instruction_set = InstructionSet::from_byte(cpu.u8()?).ok_or(StateError::InvalidData)?;
+    if instruction_set != self.instruction_set {
+      return Err(StateError::MachineMismatch);
+    }
+    let mut v = [0; 16];
+    v.copy_from_slice(cpu.bytes(16)?);
+    let i = cpu.u16()?;
+    let program_counter = cpu.u32()? as usize;
+    let delay_timer = cpu.u8()?;
+    let sound_timer = cpu.u8()?;
+    let wait_for_input = cpu.bool()?;
+    let input_register = cpu.u8()? as usize;
+    let wait_for_display = cpu.bool()?;
+    let exited = cpu.bool()?;
+    let planes = cpu.u8()?;
+
+    if input_register >= v.len() || planes > ALL_PLANES {
+      return Err(StateError::InvalidData);
+    }
+
+    let mut memory = reader.section(b"MEM ")?;
+    let memory = memory.bytes(instruction_set.memory_size())?;
+
+    let mut stack_data = reader.section(b"STCK")?;
+    let mut stack = Vec::new();
+    while !stack_data.is_empty() {
+      stack.push(stack_data.u32()? as usize);
+    }
+
+    let mut screen = reader.section(b"SCRN")?;
+    let width = screen.u16()? as usize;
+    let height = screen.u16()? as usize;
+    let resolutions = [(CHIP8_WIDTH, CHIP8_HEIGHT), (CHIP8_WIDTH, HIRES_CHIP8_HEIGHT), (SCHIP_WIDTH, SCHIP_HEIGHT)];
+    if !resolutions.contains(&(width, height)) {
+      return Err(StateError::InvalidData);
+    }
+    let pixels = screen.bytes(width * height)?;
+    if pixels.iter().any(|pixel| *pixel > ALL_PLANES) {
+      return Err(StateError::InvalidData);
+    }
+
+    let mut flags = [0; 16];
+    flags.copy_from_slice(reader.section(b"FLAG")?.bytes(16)?);
+
+    // states from before key edges were tracked don't have this section
+    let (keypad, input_key) = match reader.section(b"KEYS") {
+      Ok(mut keys) => {
+        let keypad = Keypad::from_bits([keys.u16()?, keys.u16()?, keys.u16()?]);
+        let input_key = match keys.u8()? {
+          NO_KEY => None,
+          key if (key as usize) < KEY_COUNT => Some(key),
+          _ => return Err(StateError::InvalidData),
+        };
+        (keypad, input_key)
       }
+      Err(_) => (Keypad::new(), None),
+    };
+
+    let mut random = reader.section(b"RAND")?;
+    let seed = random.u64()?;
+    let random_state = random.u64()?;
+
+    self.instruction_set = instruction_set;
+    self.v = v;
+    self.i = i;
+    self.program_counter = program_counter;
+    self.delay_timer = delay_timer;
+    self.sound_timer = sound_timer;
+    self.wait_for_input = wait_for_input;
+    self.input_register = input_register;
+    self.input_key = input_key;
+    self.keypad = keypad;
+    self.wait_for_display = wait_for_display;
+    self.exited = exited;
+    self.planes = planes;
+    self.memory.replace(memory.to_vec());
+    self.stack = stack;
+    self.flags = flags;
+
+    self.screen_buffer.resize(width, height);
+    for (index, pixel) in pixels.iter().enumerate() {
+      self.screen_buffer.set_pixel(index % width, index / width, *pixel);
     }
+    self.screen_changed = true;
+
+    self.random.reseed(seed);
+    self.random.restore_state(random_state);
+    self.update_sound();
+
+    Ok(())
+  }
+
+  // the keys currently held down, presses and releases since the last call are remembered for fx0a
+  pub fn set_input(&mut self, input: [bool; KEY_COUNT]) {
+    self.keypad.set(input);
+  }
+
+  pub fn keypad(&self) -> &Keypad {
+    &self.keypad
+  }
+
+  // true while fx0a halts the interpreter until a key is pressed and released
+  pub fn is_waiting_for_key(&self) -> bool {
+    self.wait_for_input
+  }
+
+  pub fn instructions_per_frame(&self) -> usize {
+    self.instructions_per_frame
+  }
+
+  // ignored by the vip timing model
+  pub fn set_instructions_per_frame(&mut self, instructions_per_frame: usize) {
+    self.instructions_per_frame = instructions_per_frame.max(1);
+  }
+
+  pub fn instruction_cache(&self) -> bool {
+    self.instruction_cache
+  }
+
+  pub fn set_instruction_cache(&mut self, instruction_cache: bool) {
+    self.instruction_cache = instruction_cache;
+    self.memory.clear_decoded();
+  }
+
+  pub fn engine(&self) -> Engine {
+    self.engine
+  }
+
+  // the vip timing model always interprets
+  pub fn set_engine(&mut self, engine: Engine) {
+    self.engine = engine;
+    self.blocks.clear();
+  }
+
+  pub fn timing(&self) -> Timing {
+    self.timing
+  }
+
+  pub fn set_timing(&mut self, timing: Timing) {
+    self.timing = timing;
+    self.cycle_carry = 0;
+  }
+
+  pub fn timer_frequency(&self) -> u32 {
+    self.timer_frequency
+  }
+
+  pub fn set_timer_frequency(&mut self, timer_frequency: u32) {
+    self.timer_frequency = timer_frequency.max(1);
+  }
+
+  pub fn frame_count(&self) -> u64 {
+    self.frame_count
+  }
+
+  pub fn memory(&self) -> &Memory {
+    &self.memory
+  }
 
-    for (i, byte) in rom.iter().enumerate() {
-      self.memory[MEMORY_START + i] = *byte;
+  pub fn memory_mut(&mut self) -> &mut Memory {
+    &mut self.memory
+  }
+
+  pub fn screen_buffer(&self) -> &Screen {
+    &self.screen_buffer
+  }
+
+  pub fn frame_duration(&self) -> Duration {
+    Duration::from_secs(1) / self.timer_frequency
+  }
+
+  // runs as many frames as fit into the elapsed time, the remainder is carried over to the next call
+  pub fn update(&mut self, elapsed: Duration) -> Result<TickResult<'_>, Chip8Error> {
+    let frame_duration = self.frame_duration();
+
+    self.frame_time = (self.frame_time + elapsed).min(frame_duration * MAX_CATCH_UP_FRAMES);
+
+    let mut screen_changed = false;
+    while self.frame_time >= frame_duration {
+      self.frame_time -= frame_duration;
+      screen_changed |= self.run_frame(self.instructions_per_frame)?.screen_changed;
     }
+
+    Ok(TickResult {
+      screen_buffer: &self.screen_buffer,
+      screen_changed,
+      play_sound: self.sound_timer > 0,
+      exited: self.exited,
+      waiting_for_key: self.wait_for_input,
+    })
   }
 
-  pub fn tick(&mut self, input: [bool; 16]) -> TickResult {
-    self.input = input;
+  // runs one frame worth of instructions followed by a timer tick
+  // with the vip timing model the frame ends when its machine cycles are used up instead
+  pub fn run_frame(&mut self, instructions_per_frame: usize) -> Result<TickResult<'_>, Chip8Error> {
     self.screen_changed = false;
 
-    if self.last_timer_tick.elapsed() >= Duration::from_millis(17) {
-      self.process_timer();
-      self.last_timer_tick = Instant::now();
+    match (self.timing, self.engine) {
+      (Timing::CosmacVip, _) => self.run_vip_cycles()?,
+      (Timing::Simple, Engine::Interpreter) => {
+        for _ in 0..instructions_per_frame {
+          self.step_instruction()?;
+        }
+      }
+      (Timing::Simple, Engine::Recompiler) => {
+        let mut remaining = instructions_per_frame;
+        while remaining > 0 {
+          remaining -= self.step_block(remaining)?;
+        }
+      }
     }
 
-    if self.last_tick.elapsed() >= Duration::from_millis(2) {
-      self.do_tick();
-      self.last_tick = Instant::now();
-    }
+    self.tick_timers();
+    self.frame_count += 1;
+
+    let event = FrameEvent { frame: self.frame_count, waiting_for_key: self.wait_for_input };
+    self.notify(|observer| observer.frame(&event));
 
-    TickResult {
+    Ok(TickResult {
       screen_buffer: &self.screen_buffer,
       screen_changed: self.screen_changed,
       play_sound: self.sound_timer > 0,
+      exited: self.exited,
+      waiting_for_key: self.wait_for_input,
+    })
+  }
+
+  // an instruction that overruns the frame takes its remaining cycles from the next one,
+  // waiting for a key or for the display interrupt gives up the rest of the frame
+  fn run_vip_cycles(&mut self) -> Result<(), Chip8Error> {
+    let mut cycles = self.cycle_carry;
+
+    while cycles < VIP_AVAILABLE_CYCLES && !self.exited && !self.wait_for_display {
+      if self.wait_for_input {
+        self.step_instruction()?;
+        if self.wait_for_input {
+          break;
+        }
+        continue;
+      }
+
+      let program_counter = self.program_counter;
+      let instruction = self.decode_at(program_counter)?;
+      self.step_instruction()?;
+
+      let skipped = self.program_counter != program_counter + INSTRUCTION_SIZE;
+      cycles += timing::vip_cycles(&instruction, skipped);
     }
+
+    self.cycle_carry = cycles.saturating_sub(VIP_AVAILABLE_CYCLES);
+    Ok(())
   }
 
-  fn process_timer(&mut self) {
+  pub fn tick_timers(&mut self) {
     if self.delay_timer > 0 {
-      self.delay_timer = self.delay_timer - 1;
+      self.delay_timer -= 1;
     }
 
     if self.sound_timer > 0 {
-      self.sound_timer = self.sound_timer - 1;
+      self.sound_timer -= 1;
     }
+
+    self.wait_for_display = false;
+    self.update_sound();
   }
 
-  fn do_tick(&mut self) {
+  pub fn step_instruction(&mut self) -> Result<(), Chip8Error> {
+    if self.exited {
+      return Ok(());
+    }
+
     if self.wait_for_input {
-      for (i, b) in self.input.iter().enumerate() {
-        if *b {
-          self.v[self.input_register] = i as u8;
+      if self.input_key.is_none() {
+        self.input_key = self.keypad.take_press();
+      }
+
+      if let Some(key) = self.input_key {
+        if self.keypad.take_release(key) {
+          self.v[self.input_register] = key;
 
           self.wait_for_input = false;
           self.input_register = 0;
+          self.input_key = None;
+        }
+      }
+    } else if !self.wait_for_display {
+      if self.observers.is_empty() {
+        let action = self.execute_operation()?;
+        self.advance(action);
+      } else {
+        self.execute_observed()?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn execute_observed(&mut self) -> Result<(), Chip8Error> {
+    let program_counter = self.program_counter;
+    let event = match (self.memory.peek_word(program_counter), self.decode_at(program_counter)) {
+      (Some(opcode), Ok(instruction)) => InstructionEvent { program_counter, opcode, instruction },
+      _ => {
+        //fails the same way when executed, there is nothing to report
+        let action = self.execute_operation()?;
+        self.advance(action);
+        return Ok(());
+      }
+    };
+
+    let before = self.registers();
+    self.notify(|observer| observer.before_instruction(&event, &before));
+
+    let action = self.execute_operation()?;
+    self.advance(action);
+
+    let after = self.registers();
+    self.notify(|observer| observer.after_instruction(&event, &before, &after));
+
+    Ok(())
+  }
+
+  // runs the translated block at the program counter, at most limit instructions
+  // returns how many instructions were used up, the interpreter steps in whenever the machine is waiting
+  // or the code at the program counter can't be translated
+  fn step_block(&mut self, limit: usize) -> Result<usize, Chip8Error> {
+    if self.memory.take_code_modified() {
+      self.blocks.clear();
+    }
+
+    //observers see every instruction on its own
+    if self.exited || self.wait_for_input || self.wait_for_display || !self.observers.is_empty() {
+      self.step_instruction()?;
+      return Ok(1);
+    }
+
+    let block = match self.blocks.get(self.program_counter) {
+      Some(block) => block,
+      None => match self.compile_block(self.program_counter) {
+        Some(block) => self.blocks.insert(self.program_counter, block),
+        None => {
+          self.step_instruction()?;
+          return Ok(1);
         }
       }
+    };
+
+    let mut executed = 0;
+    for instruction in block.instructions.iter().take(limit) {
+      self.memory.executed(instruction.address, instruction.size);
+      let action = (instruction.operation)(self)?;
+      self.advance(action);
+      executed += 1;
+
+      if self.memory.take_code_modified() {
+        self.blocks.clear();
+        break;
+      }
+
+      if self.wait_for_input || self.wait_for_display || self.exited {
+        break;
+      }
+    }
+
+    Ok(executed)
+  }
+
+  // translates straight line code until the first branch, None if the first instruction can't be decoded
+  fn compile_block(&mut self, start: usize) -> Option<Block> {
+    let mut block = Block::new();
+    let mut address = start;
+
+    while !block.is_full() {
+      let instruction = match self.decode_at(address) {
+        Ok(instruction) => instruction,
+        Err(_) => break,
+      };
+      self.memory.set_decoded(address, instruction);
+
+      block.instructions.push(CompiledInstruction {
+        address,
+        size: instruction.size(),
+        operation: Chip8::compile(instruction),
+      });
+      address += instruction.size();
+
+      if recompiler::ends_block(&instruction) {
+        break;
+      }
+    }
+
+    if block.instructions.is_empty() {
+      None
     } else {
-      match self.execute_operation() {
-        ProgramCounterAction::Increment => self.program_counter += INSTRUCTION_SIZE,
-        ProgramCounterAction::Skip => self.program_counter += INSTRUCTION_SIZE * 2,
-        ProgramCounterAction::Jump(adress) => self.program_counter = adress
+      Some(block)
+    }
+  }
+
+  fn advance(&mut self, action: ProgramCounterAction) {
+    match action {
+      ProgramCounterAction::Increment => self.program_counter += INSTRUCTION_SIZE,
+      ProgramCounterAction::Skip => self.program_counter += INSTRUCTION_SIZE + self.next_instruction_size(),
+      ProgramCounterAction::Jump(adress) => self.program_counter = adress
+    }
+  }
+
+  //skips have to jump over the whole 4 byte f000 nnnn instruction
+  fn next_instruction_size(&self) -> usize {
+    let next = self.program_counter + INSTRUCTION_SIZE;
+    if self.instruction_set == InstructionSet::XoChip && self.memory.peek_word(next) == Some(LONG_I_OPCODE) {
+      INSTRUCTION_SIZE * 2
+    } else {
+      INSTRUCTION_SIZE
+    }
+  }
+
+  fn key_pressed(&self, x: usize) -> Result<bool, Chip8Error> {
+    let key = self.v[x];
+    self.keypad.is_down(key as usize).ok_or(Chip8Error::InvalidKey { key })
+  }
+
+  fn execute_operation(&mut self) -> Result<ProgramCounterAction, Chip8Error> {
+    if self.instruction_cache {
+      if let Some(instruction) = self.memory.fetch_decoded(self.program_counter) {
+        return self.execute(instruction);
       }
     }
+
+    let instruction = self.decode_operation()?;
+    if self.instruction_cache {
+      self.memory.set_decoded(self.program_counter, instruction);
+    }
+
+    self.execute(instruction)
   }
 
-  fn execute_operation(&mut self) -> ProgramCounterAction {
-    let op = (self.memory[self.program_counter as usize] as u16) << 8 | self.memory[self.program_counter as usize + 1] as u16;
-
-    let half_bytes = (
-      ((op & 0xF000) >> 12) as u8,
-      ((op & 0x0F00) >> 8) as u8,
-      ((op & 0x00F0) >> 4) as u8,
-      (op & 0x000F) as u8
-    );
-
-    let addr = (op & 0x0FFF) as usize;
-    let byte = (op & 0x00FF) as usize;
-
-    let x = half_bytes.1 as usize;
-    let y = half_bytes.2 as usize;
-    let nibble = half_bytes.3 as usize;
-
-    match half_bytes {
-      (0x0, 0x0, 0xE, 0x0) => self.op_00e0(), //clear screen
-      (0x0, 0x0, 0xE, 0xE) => self.op_00ee(), //return from subroutine
-      (0x0,   _,   _,   _) => self.op_0nnn(addr), //system routing - NOself.op
-      (0x1,   _,   _,   _) => self.op_1nnn(addr), //jump to addr
-      (0x2,   _,   _,   _) => self.op_2nnn(addr), //call add (subroutine)
-      (0x3,   _,   _,   _) => self.op_3xkk(x, byte), //skip if vx == kk
-      (0x4,   _,   _,   _) => self.op_4xkk(x, byte), //skip if vx != kk
-      (0x5,   _,   _, 0x0) => self.op_5xy0(x, y), //skip if vx == vy
-      (0x6,   _,   _,   _) => self.op_6xkk(x, byte), //set vx = kk
-      (0x7,   _,   _,   _) => self.op_7xkk(x, byte), //set vx = vx + kk
-      (0x8,   _,   _, 0x0) => self.op_8xy0(x, y), //set vx = vy
-      (0x8,   _,   _, 0x1) => self.op_8xy1(x, y), //set vx = vx | vy
-      (0x8,   _,   _, 0x2) => self.op_8xy2(x, y), //set vx = vx & vy
-      (0x8,   _,   _, 0x3) => self.op_8xy3(x, y), //set vx = vx ^ vy
-      (0x8,   _,   _, 0x4) => self.op_8xy4(x, y), //set vx = vx + vy, only 8 bits are kept, vf = 1 if > 256 else 0
-      (0x8,   _,   _, 0x5) => self.op_8xy5(x, y), //set vx = vx - vy, if vx > vy vf = 1
-      (0x8,   _,   _, 0x6) => self.op_8xy6(x, y), //set vx = vx / 2; if uneven vf = 1
-      (0x8,   _,   _, 0x7) => self.op_8xy7(x, y), //set vx = vy - vx; if vy > vx vf = 1
-      (0x8,   _,   _, 0xE) => self.op_8xye(x, y), //set vx = vx * 2; if most significant bit = 1 then vf = 1
-      (0x9,   _,   _, 0x0) => self.op_9xy0(x, y), //skip if vx != vy
-      (0xA,   _,   _,   _) => self.op_annn(addr), //set i = nnn
-      (0xB,   _,   _,   _) => self.op_bnnn(addr), //jump to nnn + v0
-      (0xC,   _,   _,   _) => self.op_cxkk(x, byte), //set vx random byte + kkk
-      (0xD,   _,   _,   _) => self.op_dxyn(x, y, nibble), //display n-byte spring starting at i at (vx,vy), vf = 1 if erased
-      (0xE,   _, 0x9, 0xE) => self.op_ex9e(x), //skip if key press == vx
-      (0xE,   _, 0xA, 0x1) => self.op_exa1(x), //skip if key not pressed == vx
-      (0xF,   _, 0x0, 0x7) => self.op_fx07(x), //vx = delay timer
-      (0xF,   _, 0x0, 0xA) => self.op_fx0a(x), //wait for keypress and store in vx
-      (0xF,   _, 0x1, 0x5) => self.op_fx15(x), //set delay timer = vx
-      (0xF,   _, 0x1, 0x8) => self.op_fx18(x), //set sound timer = vx
-      (0xF,   _, 0x1, 0xE) => self.op_fx1e(x), //set i = i + vx
-      (0xF,   _, 0x2, 0x9) => self.op_fx29(x), //set i = location of sprite for digit vx
-      (0xF,   _, 0x3, 0x3) => self.op_fx33(x), //set i bcd vx (i = 100, i+1 = 10, i+2 = 1)
-      (0xF,   _, 0x5, 0x5) => self.op_fx55(x), //write v0 to vx to memory starting at i
-      (0xF,   _, 0x6, 0x5) => self.op_fx65(x), //read v0 to vx from memory starting at i
-      (  _,   _,   _,   _) => {
-        println!("Unrecognized command {:X} @ {:X}", op, self.program_counter);
-        ProgramCounterAction::Increment
+  fn decode_operation(&mut self) -> Result<Instruction, Chip8Error> {
+    let op = self.memory.fetch(self.program_counter)?;
+    let instruction = self.decode_opcode(self.program_counter, op)?;
+    self.memory.cover_operand(self.program_counter, instruction.size());
+    Ok(instruction)
+  }
+
+  // decodes without touching the watchpoints
+  fn decode_at(&self, address: usize) -> Result<Instruction, Chip8Error> {
+    let op = self.memory.peek_word(address).ok_or(Chip8Error::ProgramCounterOutOfRange { address })?;
+    self.decode_opcode(address, op)
+  }
+
+  fn decode_opcode(&self, address: usize, mut op: u16) -> Result<Instruction, Chip8Error> {
+    if self.instruction_set == InstructionSet::HiresChip8 {
+      if address == self.program_start && op == HIRES_START_OPCODE {
+        op = HIRES_PROGRAM_OPCODE;
+      } else if op == HIRES_CLEAR_OPCODE {
+        op = Instruction::ClearScreen.encode();
       }
     }
+
+    let mut instruction = Instruction::decode(op)
+      .map_err(|UnknownOpcode(opcode)| Chip8Error::UnknownOpcode { opcode, address })?;
+
+    if !self.instruction_set.supports(&instruction) {
+      return match op & 0xF000 {
+        0x0000 => Ok(Instruction::Sys(op & 0x0FFF)),
+        _ => Err(Chip8Error::UnknownOpcode { opcode: op, address }),
+      };
+    }
+
+    if op == LONG_I_OPCODE {
+      let next = self.memory.peek_word(address + INSTRUCTION_SIZE)
+        .ok_or(Chip8Error::ProgramCounterOutOfRange { address })?;
+      instruction = Instruction::decode_long(op, next)
+        .map_err(|UnknownOpcode(opcode)| Chip8Error::UnknownOpcode { opcode, address })?;
+    }
+
+    Ok(instruction)
+  }
+
+  fn execute(&mut self, instruction: Instruction) -> Result<ProgramCounterAction, Chip8Error> {
+    match instruction {
+      Instruction::ClearScreen => self.op_00e0(), //clear screen
+      Instruction::Return => self.op_00ee(), //return from subroutine
+      Instruction::Sys(addr) => self.op_0nnn(addr as usize), //system routing - NOOP
+      Instruction::Jump(addr) => self.op_1nnn(addr as usize), //jump to addr
+      Instruction::Call(addr) => self.op_2nnn(addr as usize), //call add (subroutine)
+      Instruction::SkipEqualByte(x, byte) => self.op_3xkk(x as usize, byte as usize), //skip if vx == kk
+      Instruction::SkipNotEqualByte(x, byte) => self.op_4xkk(x as usize, byte as usize), //skip if vx != kk
+      Instruction::SkipEqual(x, y) => self.op_5xy0(x as usize, y as usize), //skip if vx == vy
+      Instruction::LoadByte(x, byte) => self.op_6xkk(x as usize, byte as usize), //set vx = kk
+      Instruction::AddByte(x, byte) => self.op_7xkk(x as usize, byte as usize), //set vx = vx + kk
+      Instruction::Load(x, y) => self.op_8xy0(x as usize, y as usize), //set vx = vy
+      Instruction::Or(x, y) => self.op_8xy1(x as usize, y as usize), //set vx = vx | vy
+      Instruction::And(x, y) => self.op_8xy2(x as usize, y as usize), //set vx = vx & vy
+      Instruction::Xor(x, y) => self.op_8xy3(x as usize, y as usize), //set vx = vx ^ vy
+      Instruction::Add(x, y) => self.op_8xy4(x as usize, y as usize), //set vx = vx + vy, only 8 bits are kept, vf = 1 if > 256 else 0
+      Instruction::Sub(x, y) => self.op_8xy5(x as usize, y as usize), //set vx = vx - vy, if vx > vy vf = 1
+      Instruction::ShiftRight(x, y) => self.op_8xy6(x as usize, y as usize), //set vx = vx / 2; if uneven vf = 1
+      Instruction::SubReverse(x, y) => self.op_8xy7(x as usize, y as usize), //set vx = vy - vx; if vy > vx vf = 1
+      Instruction::ShiftLeft(x, y) => self.op_8xye(x as usize, y as usize), //set vx = vx * 2; if most significant bit = 1 then vf = 1
+      Instruction::SkipNotEqual(x, y) => self.op_9xy0(x as usize, y as usize), //skip if vx != vy
+      Instruction::LoadI(addr) => self.op_annn(addr as usize), //set i = nnn
+      Instruction::JumpOffset(addr) => self.op_bnnn((addr >> 8) as usize, addr as usize), //jump to nnn + v0
+      Instruction::Random(x, byte) => self.op_cxkk(x as usize, byte as usize), //set vx random byte + kkk
+      Instruction::Draw(x, y, nibble) => self.op_dxyn(x as usize, y as usize, nibble as usize), //display n-byte spring starting at i at (vx,vy), vf = 1 if erased
+      Instruction::SkipKeyPressed(x) => self.op_ex9e(x as usize), //skip if key press == vx
+      Instruction::SkipKeyNotPressed(x) => self.op_exa1(x as usize), //skip if key not pressed == vx
+      Instruction::LoadDelayTimer(x) => self.op_fx07(x as usize), //vx = delay timer
+      Instruction::WaitForKey(x) => self.op_fx0a(x as usize), //wait for keypress and store in vx
+      Instruction::SetDelayTimer(x) => self.op_fx15(x as usize), //set delay timer = vx
+      Instruction::SetSoundTimer(x) => self.op_fx18(x as usize), //set sound timer = vx
+      Instruction::AddI(x) => self.op_fx1e(x as usize), //set i = i + vx
+      Instruction::LoadFont(x) => self.op_fx29(x as usize), //set i = location of sprite for digit vx
+      Instruction::StoreBcd(x) => self.op_fx33(x as usize), //set i bcd vx (i = 100, i+1 = 10, i+2 = 1)
+      Instruction::StoreRegisters(x) => self.op_fx55(x as usize), //write v0 to vx to memory starting at i
+      Instruction::LoadRegisters(x) => self.op_fx65(x as usize), //read v0 to vx from memory starting at i
+      Instruction::ScrollDown(nibble) => self.op_00cn(nibble as usize), //scroll down n lines
+      Instruction::ScrollRight => self.op_00fb(), //scroll right 4 pixels
+      Instruction::ScrollLeft => self.op_00fc(), //scroll left 4 pixels
+      Instruction::Exit => self.op_00fd(), //exit the interpreter
+      Instruction::LowResolution => self.op_00fe(), //switch to 64x32
+      Instruction::HighResolution => self.op_00ff(), //switch to 128x64
+      Instruction::LoadBigFont(x) => self.op_fx30(x as usize), //set i = location of big sprite for digit vx
+      Instruction::StoreFlags(x) => self.op_fx75(x as usize), //write v0 to vx to the rpl flags
+      Instruction::LoadFlags(x) => self.op_fx85(x as usize), //read v0 to vx from the rpl flags
+      Instruction::ScrollUp(nibble) => self.op_00dn(nibble as usize), //scroll up n lines
+      Instruction::SaveRange(x, y) => self.op_5xy2(x as usize, y as usize), //write vx to vy to memory starting at i
+      Instruction::LoadRange(x, y) => self.op_5xy3(x as usize, y as usize), //read vx to vy from memory starting at i
+      Instruction::LoadLongI(addr) => self.op_f000(addr as usize), //set i = nnnn
+      Instruction::SelectPlanes(planes) => self.op_fn01(planes), //select the bitplanes to draw on
+    }
+  }
+
+  // binds the operands of an instruction into a closure for the recompiler
+  fn compile(instruction: Instruction) -> Operation {
+    match instruction {
+      Instruction::ClearScreen => Box::new(|chip8| chip8.op_00e0()),
+      Instruction::Return => Box::new(|chip8| chip8.op_00ee()),
+      Instruction::Sys(addr) => Box::new(move |chip8| chip8.op_0nnn(addr as usize)),
+      Instruction::Jump(addr) => Box::new(move |chip8| chip8.op_1nnn(addr as usize)),
+      Instruction::Call(addr) => Box::new(move |chip8| chip8.op_2nnn(addr as usize)),
+      Instruction::SkipEqualByte(x, byte) => Box::new(move |chip8| chip8.op_3xkk(x as usize, byte as usize)),
+      Instruction::SkipNotEqualByte(x, byte) => Box::new(move |chip8| chip8.op_4xkk(x as usize, byte as usize)),
+      Instruction::SkipEqual(x, y) => Box::new(move |chip8| chip8.op_5xy0(x as usize, y as usize)),
+      Instruction::LoadByte(x, byte) => Box::new(move |chip8| chip8.op_6xkk(x as usize, byte as usize)),
+      Instruction::AddByte(x, byte) => Box::new(move |chip8| chip8.op_7xkk(x as usize, byte as usize)),
+      Instruction::Load(x, y) => Box::new(move |chip8| chip8.op_8xy0(x as usize, y as usize)),
+      Instruction::Or(x, y) => Box::new(move |chip8| chip8.op_8xy1(x as usize, y as usize)),
+      Instruction::And(x, y) => Box::new(move |chip8| chip8.op_8xy2(x as usize, y as usize)),
+      Instruction::Xor(x, y) => Box::new(move |chip8| chip8.op_8xy3(x as usize, y as usize)),
+      Instruction::Add(x, y) => Box::new(move |chip8| chip8.op_8xy4(x as usize, y as usize)),
+      Instruction::Sub(x, y) => Box::new(move |chip8| chip8.op_8xy5(x as usize, y as usize)),
+      Instruction::ShiftRight(x, y) => Box::new(move |chip8| chip8.op_8xy6(x as usize, y as usize)),
+      Instruction::SubReverse(x, y) => Box::new(move |chip8| chip8.op_8xy7(x as usize, y as usize)),
+      Instruction::ShiftLeft(x, y) => Box::new(move |chip8| chip8.op_8xye(x as usize, y as usize)),
+      Instruction::SkipNotEqual(x, y) => Box::new(move |chip8| chip8.op_9xy0(x as usize, y as usize)),
+      Instruction::LoadI(addr) => Box::new(move |chip8| chip8.op_annn(addr as usize)),
+      Instruction::JumpOffset(addr) => Box::new(move |chip8| chip8.op_bnnn((addr >> 8) as usize, addr as usize)),
+      Instruction::Random(x, byte) => Box::new(move |chip8| chip8.op_cxkk(x as usize, byte as usize)),
+      Instruction::Draw(x, y, nibble) => Box::new(move |chip8| chip8.op_dxyn(x as usize, y as usize, nibble as usize)),
+      Instruction::SkipKeyPressed(x) => Box::new(move |chip8| chip8.op_ex9e(x as usize)),
+      Instruction::SkipKeyNotPressed(x) => Box::new(move |chip8| chip8.op_exa1(x as usize)),
+      Instruction::LoadDelayTimer(x) => Box::new(move |chip8| chip8.op_fx07(x as usize)),
+      Instruction::WaitForKey(x) => Box::new(move |chip8| chip8.op_fx0a(x as usize)),
+      Instruction::SetDelayTimer(x) => Box::new(move |chip8| chip8.op_fx15(x as usize)),
+      Instruction::SetSoundTimer(x) => Box::new(move |chip8| chip8.op_fx18(x as usize)),
+      Instruction::AddI(x) => Box::new(move |chip8| chip8.op_fx1e(x as usize)),
+      Instruction::LoadFont(x) => Box::new(move |chip8| chip8.op_fx29(x as usize)),
+      Instruction::StoreBcd(x) => Box::new(move |chip8| chip8.op_fx33(x as usize)),
+      Instruction::StoreRegisters(x) => Box::new(move |chip8| chip8.op_fx55(x as usize)),
+      Instruction::LoadRegisters(x) => Box::new(move |chip8| chip8.op_fx65(x as usize)),
+      Instruction::ScrollDown(nibble) => Box::new(move |chip8| chip8.op_00cn(nibble as usize)),
+      Instruction::ScrollRight => Box::new(|chip8| chip8.op_00fb()),
+      Instruction::ScrollLeft => Box::new(|chip8| chip8.op_00fc()),
+      Instruction::Exit => Box::new(|chip8| chip8.op_00fd()),
+      Instruction::LowResolution => Box::new(|chip8| chip8.op_00fe()),
+      Instruction::HighResolution => Box::new(|chip8| chip8.op_00ff()),
+      Instruction::LoadBigFont(x) => Box::new(move |chip8| chip8.op_fx30(x as usize)),
+      Instruction::StoreFlags(x) => Box::new(move |chip8| chip8.op_fx75(x as usize)),
+      Instruction::LoadFlags(x) => Box::new(move |chip8| chip8.op_fx85(x as usize)),
+      Instruction::ScrollUp(nibble) => Box::new(move |chip8| chip8.op_00dn(nibble as usize)),
+      Instruction::SaveRange(x, y) => Box::new(move |chip8| chip8.op_5xy2(x as usize, y as usize)),
+      Instruction::LoadRange(x, y) => Box::new(move |chip8| chip8.op_5xy3(x as usize, y as usize)),
+      Instruction::LoadLongI(addr) => Box::new(move |chip8| chip8.op_f000(addr as usize)),
+      Instruction::SelectPlanes(planes) => Box::new(move |chip8| chip8.op_fn01(planes)),
+    }
   }
 
-  fn op_0nnn(&mut self, _addr: usize) -> ProgramCounterAction { //system routing - NOOP
-    ProgramCounterAction::Increment
+  fn op_0nnn(&mut self, _addr: usize) -> Result<ProgramCounterAction, Chip8Error> { //system routing - NOOP
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_00e0(&mut self, ) -> ProgramCounterAction { //clear screen
-    self.screen_buffer = [[false; 64]; 32];
+  fn op_00e0(&mut self, ) -> Result<ProgramCounterAction, Chip8Error> { //clear screen
+    self.screen_buffer.clear(self.planes);
     self.screen_changed = true;
-    ProgramCounterAction::Increment
+
+    let planes = self.planes;
+    self.notify(|observer| observer.clear_screen(planes));
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_00ee(&mut self, ) -> ProgramCounterAction { //return from subroutine
-    ProgramCounterAction::Jump(self.stack.pop().unwrap())
+  fn op_00ee(&mut self, ) -> Result<ProgramCounterAction, Chip8Error> { //return from subroutine
+    let address = match self.stack.pop() {
+      Some(address) => address,
+      None => return Err(Chip8Error::StackUnderflow { address: self.program_counter }),
+    };
+
+    //roms may have modified the stack in memory, so that copy wins
+    if self.stack_in_memory {
+      let entry = VIP_STACK_START + self.stack.len() * 2;
+      let high = self.memory.read(entry)? as usize;
+      let low = self.memory.read(entry + 1)? as usize;
+      return Ok(ProgramCounterAction::Jump(high << 8 | low));
+    }
+
+    Ok(ProgramCounterAction::Jump(address))
   }
 
-  fn op_1nnn(&mut self, addr: usize) -> ProgramCounterAction { //jump to addr
-    ProgramCounterAction::Jump(addr)
+  fn op_1nnn(&mut self, addr: usize) -> Result<ProgramCounterAction, Chip8Error> { //jump to addr
+    Ok(ProgramCounterAction::Jump(addr))
   }
 
-  fn op_2nnn(&mut self, addr: usize) -> ProgramCounterAction { //call subroutine
-    self.stack.push(self.program_counter + INSTRUCTION_SIZE);
-    ProgramCounterAction::Jump(addr)
+  fn op_2nnn(&mut self, addr: usize) -> Result<ProgramCounterAction, Chip8Error> { //call subroutine
+    if self.stack.len() >= self.stack_depth {
+      return Err(Chip8Error::StackOverflow { address: self.program_counter });
+    }
+
+    let address = self.program_counter + INSTRUCTION_SIZE;
+    if self.stack_in_memory {
+      let entry = VIP_STACK_START + self.stack.len() * 2;
+      self.memory.write(entry, (address >> 8) as u8)?;
+      self.memory.write(entry + 1, address as u8)?;
+    }
+
+    self.stack.push(address);
+    Ok(ProgramCounterAction::Jump(addr))
   }
 
-  fn op_3xkk(&mut self, x: usize, byte: usize)-> ProgramCounterAction { //skip if vx == kk
+  fn op_3xkk(&mut self, x: usize, byte: usize)-> Result<ProgramCounterAction, Chip8Error> { //skip if vx == kk
     if self.v[x] == byte as u8 {
-      ProgramCounterAction::Skip
+      Ok(ProgramCounterAction::Skip)
     } else {
-      ProgramCounterAction::Increment
+      Ok(ProgramCounterAction::Increment)
     }
   }
 
-  fn op_4xkk(&mut self, x: usize, byte: usize) -> ProgramCounterAction { //skip if vx != kk
+  fn op_4xkk(&mut self, x: usize, byte: usize) -> Result<ProgramCounterAction, Chip8Error> { //skip if vx != kk
     if self.v[x] != byte as u8 {
-      ProgramCounterAction::Skip
+      Ok(ProgramCounterAction::Skip)
     } else {
-      ProgramCounterAction::Increment
+      Ok(ProgramCounterAction::Increment)
     }
   }
 
-  fn op_5xy0(&mut self, x: usize, y: usize) -> ProgramCounterAction { //skip if vx == vy
+  fn op_5xy0(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //skip if vx == vy
     if self.v[x] == self.v[y] {
-      ProgramCounterAction::Skip
+      Ok(ProgramCounterAction::Skip)
     } else {
-      ProgramCounterAction::Increment
+      Ok(ProgramCounterAction::Increment)
     }
   }
 
-  fn op_6xkk(&mut self, x: usize, byte: usize) -> ProgramCounterAction { //set vx = kk
+  fn op_6xkk(&mut self, x: usize, byte: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = kk
     self.v[x] = byte as u8;
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_7xkk(&mut self, x: usize, byte: usize) -> ProgramCounterAction { //set vx = vx + kk
+  fn op_7xkk(&mut self, x: usize, byte: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vx + kk
     self.v[x] = (self.v[x] as usize + byte) as u8;
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
 
   }
 
-  fn op_8xy0(&mut self, x: usize, y: usize) -> ProgramCounterAction { //set vx = vy
+  fn op_8xy0(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vy
     self.v[x] = self.v[y];
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
 
   }
 
-  fn op_8xy1(&mut self, x: usize, y: usize) -> ProgramCounterAction { //set vx = vx | vy
-    self.v[x] = self.v[x] | self.v[y];
-    ProgramCounterAction::Increment
+  fn op_8xy1(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vx | vy
+    self.v[x] |= self.v[y];
+    if self.quirks.vf_reset {
+      self.v[0xF] = 0;
+    }
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_8xy2(&mut self, x: usize, y: usize) -> ProgramCounterAction { //set vx = vx & vy
-    self.v[x] = self.v[x] & self.v[y];
-    ProgramCounterAction::Increment
+  fn op_8xy2(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vx & vy
+    self.v[x] &= self.v[y];
+    if self.quirks.vf_reset {
+      self.v[0xF] = 0;
+    }
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_8xy3(&mut self, x: usize, y: usize) -> ProgramCounterAction { //set vx = vx ^ vy
-    self.v[x] = self.v[x] ^ self.v[y];
-    ProgramCounterAction::Increment
+  fn op_8xy3(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vx ^ vy
+    self.v[x] ^= self.v[y];
+    if self.quirks.vf_reset {
+      self.v[0xF] = 0;
+    }
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_8xy4(&mut self, x: usize, y: usize) -> ProgramCounterAction { //set vx = vx + vy, only 8 bits are kept-> ProgramCounterAction { vf = 1 if > 256 else 0
+  fn op_8xy4(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vx + vy, only 8 bits are kept-> Result<ProgramCounterAction, Chip8Error> { vf = 1 if > 256 else 0
     let sum = self.v[x] as u16 + self.v[y] as u16;
     self.v[0xF] = if sum > 255 { 1 } else { 0 };
     self.v[x] = (sum & 0x00FF) as u8;
 
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_8xy5(&mut self, x: usize, y: usize) -> ProgramCounterAction { //set vx = vx - vy, if vx > vy vf = 1
+  fn op_8xy5(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vx - vy, if vx > vy vf = 1
     if self.v[x] > self.v[y] {
-      self.v[x] = self.v[x] - self.v[y];
+      self.v[x] -= self.v[y];
       self.v[0xF] = 1;
     } else {
       self.v[x] = 0;
       self.v[0xF] = 0;
     }
 
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_8xy6(&mut self, x: usize, _y: usize) -> ProgramCounterAction { //set vx = vx / 2; if uneven vf = 1
-    self.v[0xF] = self.v[x] & 0b00000001;
-    self.v[x] = self.v[x] >> 1;
+  fn op_8xy6(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vx / 2; if uneven vf = 1
+    let value = if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] };
+    self.v[x] = value >> 1;
+    self.v[0xF] = value & 0b00000001;
 
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_8xy7(&mut self, x: usize, y: usize) -> ProgramCounterAction { //set vx = vy - vx; if vy > vx vf = 1
+  fn op_8xy7(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vy - vx; if vy > vx vf = 1
     if self.v[x] < self.v[y] {
       self.v[x] = self.v[y] - self.v[x];
       self.v[0xF] = 1;
@@ -303,151 +1205,271 @@ impl Chip8 {
       self.v[0xF] = 0;
     }
 
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_8xye(&mut self, x: usize, _y: usize) -> ProgramCounterAction { //set vx = vx * 2; if most significant bit = 1 then vf = 1
-    self.v[0xF] = (self.v[x] & 0b10000000) >> 7;
-    self.v[x] = self.v[x] << 1;
+  fn op_8xye(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx = vx * 2; if most significant bit = 1 then vf = 1
+    let value = if self.quirks.shift_uses_vy { self.v[y] } else { self.v[x] };
+    self.v[x] = value << 1;
+    self.v[0xF] = (value & 0b10000000) >> 7;
 
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_9xy0(&mut self, x: usize, y: usize) -> ProgramCounterAction { //skip if vx != vy
+  fn op_9xy0(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //skip if vx != vy
     if self.v[x] != self.v[y] {
-      ProgramCounterAction::Skip
+      Ok(ProgramCounterAction::Skip)
     } else {
-      ProgramCounterAction::Increment
+      Ok(ProgramCounterAction::Increment)
     }
   }
 
-  fn op_annn(&mut self, addr: usize) -> ProgramCounterAction { //set i = nnn
+  fn op_annn(&mut self, addr: usize) -> Result<ProgramCounterAction, Chip8Error> { //set i = nnn
     self.i = addr as u16;
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_bnnn(&mut self, addr: usize) -> ProgramCounterAction { //jump to nnn + v0
-    ProgramCounterAction::Jump(addr + self.v[0] as usize)
+  fn op_bnnn(&mut self, x: usize, addr: usize) -> Result<ProgramCounterAction, Chip8Error> { //jump to nnn + v0
+    let offset = if self.quirks.jump_uses_vx { self.v[x] } else { self.v[0] };
+    Ok(ProgramCounterAction::Jump(addr + offset as usize))
   }
 
-  fn op_cxkk(&mut self, x: usize, byte: usize) -> ProgramCounterAction { //set vx random byte & kkk
-    self.v[x] = rand::random::<u8>() & byte as u8;
+  fn op_cxkk(&mut self, x: usize, byte: usize) -> Result<ProgramCounterAction, Chip8Error> { //set vx random byte & kkk
+    self.v[x] = self.random.next_byte() & byte as u8;
 
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_dxyn(&mut self, x: usize, y: usize, nibble: usize) -> ProgramCounterAction { //display n-byte sprite starting at i at (vx,vy), vf = 1 if erased
+  fn op_dxyn(&mut self, x: usize, y: usize, nibble: usize) -> Result<ProgramCounterAction, Chip8Error> { //display n-byte sprite starting at i at (vx,vy), vf = 1 if erased
     let mut deleted = false;
 
-    for line in 0..nibble {
-      let y = (self.v[y] as usize + line) % CHIP8_HEIGHT;
-      let byte = self.memory[self.i as usize + line];
+    let width = self.screen_buffer.width();
+    let height = self.screen_buffer.height();
+
+    //super-chip draws a 16x16 sprite when n = 0
+    let (sprite_width, sprite_height) = if nibble == 0 && self.instruction_set.extends_superchip() {
+      (16, 16)
+    } else {
+      (8, nibble)
+    };
+    let bytes_per_line = sprite_width / 8;
+
+    let start_x = self.v[x] as usize % width;
+    let start_y = self.v[y] as usize % height;
+
+    //xo-chip draws the sprite once per selected plane, the sprite data for the planes follows each other
+    let planes = self.planes;
+    let mut address = self.i as usize;
+    for plane in (0..PLANE_COUNT).map(|plane| 1 << plane).filter(|plane| planes & plane != 0) {
+      for line in 0..sprite_height {
+        if self.quirks.clip_sprites && start_y + line >= height {
+          break;
+        }
+
+        let y = (start_y + line) % height;
 
-      for offset in 0..8 {
-        let bit = ((byte >> 7 - offset) & 0b00000001) > 0;
-        let x = (self.v[x] as usize + offset) % CHIP8_WIDTH;
+        for column in 0..bytes_per_line {
+          let byte = self.memory.read(address + line * bytes_per_line + column)?;
 
-        let before = self.screen_buffer[y][x];
-        self.screen_buffer[y][x] ^= bit;
+          for bit in 0..8 {
+            let offset = column * 8 + bit;
+            if self.quirks.clip_sprites && start_x + offset >= width {
+              break;
+            }
 
-        deleted = deleted || (before && !self.screen_buffer[y][x])
+            if (byte >> (7 - bit)) & 0b00000001 > 0 {
+              let x = (start_x + offset) % width;
+              deleted |= self.screen_buffer.toggle(x, y, plane);
+            }
+          }
+        }
       }
+
+      address += sprite_height * bytes_per_line;
     }
 
     self.v[0xF] = if deleted { 1 } else { 0 };
     self.screen_changed = true;
+    self.wait_for_display = self.quirks.display_wait;
 
-    ProgramCounterAction::Increment
+    let event = DrawEvent { x: start_x, y: start_y, width: sprite_width, height: sprite_height, planes, collision: deleted };
+    self.notify(|observer| observer.draw(&event));
+
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_ex9e(&mut self, x: usize) -> ProgramCounterAction { //skip if key press == vx
-    if self.input[self.v[x] as usize] {
-      ProgramCounterAction::Skip
+  fn op_ex9e(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //skip if key press == vx
+    if self.key_pressed(x)? {
+      Ok(ProgramCounterAction::Skip)
     } else {
-      ProgramCounterAction::Increment
+      Ok(ProgramCounterAction::Increment)
     }
   }
 
-  fn op_exa1(&mut self, x: usize) -> ProgramCounterAction { //skip if key not pressed == vx
-    if !self.input[self.v[x] as usize] {
-      ProgramCounterAction::Skip
+  fn op_exa1(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //skip if key not pressed == vx
+    if !self.key_pressed(x)? {
+      Ok(ProgramCounterAction::Skip)
     } else {
-      ProgramCounterAction::Increment
+      Ok(ProgramCounterAction::Increment)
     }
   }
 
-  fn op_fx07(&mut self, x: usize) -> ProgramCounterAction { //vx = delay timer
+  fn op_fx07(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //vx = delay timer
     self.v[x] = self.delay_timer;
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_fx0a(&mut self, x: usize) -> ProgramCounterAction { //wait for keypress and store in vx
+  fn op_fx0a(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //wait for keypress and release and store in vx
+    self.keypad.clear_edges(); //keys held from before have to be pressed again
     self.wait_for_input = true;
     self.input_register = x;
-    ProgramCounterAction::Increment
+    self.input_key = None;
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_fx15(&mut self, x: usize) -> ProgramCounterAction { //set delay timer = vx
+  fn op_fx15(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //set delay timer = vx
     self.delay_timer = self.v[x];
-    ProgramCounterAction::Increment
+
+    let value = self.delay_timer;
+    self.notify(|observer| observer.timer_write(Timer::Delay, value));
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_fx18(&mut self, x: usize) -> ProgramCounterAction { //set sound timer = vx
+  fn op_fx18(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //set sound timer = vx
     self.sound_timer = self.v[x];
-    ProgramCounterAction::Increment
+
+    let value = self.sound_timer;
+    self.notify(|observer| observer.timer_write(Timer::Sound, value));
+    self.update_sound();
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_fx1e(&mut self, x: usize) -> ProgramCounterAction { //set i = i + vx
-    self.i += self.v[x] as u16;
-    self.v[0xF] = if self.i > 0x0F00 { 1 } else { 0 };
-    ProgramCounterAction::Increment
+  fn op_fx1e(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //set i = i + vx
+    self.i = self.i.wrapping_add(self.v[x] as u16);
+    if self.quirks.i_overflow_flag {
+      self.v[0xF] = if self.i > 0x0F00 { 1 } else { 0 };
+    }
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_fx29(&mut self, x: usize) -> ProgramCounterAction { //set i = location of sprite for digit vx
-    self.i = self.v[x] as u16 * 5;
-    ProgramCounterAction::Increment
+  fn op_fx29(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //set i = location of sprite for digit vx
+    self.i = (self.font_address + (self.v[x] as usize & 0xF) * SMALL_GLYPH_SIZE) as u16;
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_fx33(&mut self, x: usize) -> ProgramCounterAction { //set i bcd vx, i = 100, i+1 = 10, i+2 = 1
-    self.memory[self.i as usize] = self.v[x] / 100;
-    self.memory[self.i as usize + 1] = (self.v[x] % 100) / 10;
-    self.memory[self.i as usize + 2] = self.v[x] % 10;
+  fn op_fx33(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //set i bcd vx, i = 100, i+1 = 10, i+2 = 1
+    self.memory.write(self.i as usize, self.v[x] / 100)?;
+    self.memory.write(self.i as usize + 1, (self.v[x] % 100) / 10)?;
+    self.memory.write(self.i as usize + 2, self.v[x] % 10)?;
 
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_fx55(&mut self, x: usize) -> ProgramCounterAction { //write v0 to vx to memory starting at i
-    for offset in 0..x {
-      self.memory[self.i as usize + offset] = self.v[offset];
+  fn op_fx55(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //write v0 to vx to memory starting at i
+    for offset in 0..=x {
+      self.memory.write(self.i as usize + offset, self.v[offset])?;
     }
 
-    ProgramCounterAction::Increment
+    if self.quirks.load_store_increments_i {
+      self.i = self.i.wrapping_add(x as u16 + 1);
+    }
+
+    Ok(ProgramCounterAction::Increment)
   }
 
-  fn op_fx65(&mut self, x: usize) -> ProgramCounterAction { //read v0 to vx from memory starting at i
-    for offset in 0..x {
-      self.v[offset] = self.memory[self.i as usize + offset];
+  fn op_fx65(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //read v0 to vx from memory starting at i
+    for offset in 0..=x {
+      self.v[offset] = self.memory.read(self.i as usize + offset)?;
+    }
+
+    if self.quirks.load_store_increments_i {
+      self.i = self.i.wrapping_add(x as u16 + 1);
     }
 
-    ProgramCounterAction::Increment
+    Ok(ProgramCounterAction::Increment)
   }
-}
 
-const DIGIT_0: [u8; 5] = [0xF0, 0x90, 0x90, 0x90, 0xF0];
-const DIGIT_1: [u8; 5] = [0x20, 0x60, 0x20, 0x20, 0x70];
-const DIGIT_2: [u8; 5] = [0xF0, 0x10, 0xF0, 0x80, 0xF0];
-const DIGIT_3: [u8; 5] = [0xF0, 0x10, 0xF0, 0x10, 0xF0];
-const DIGIT_4: [u8; 5] = [0x90, 0x90, 0xF0, 0x10, 0x10];
-const DIGIT_5: [u8; 5] = [0xF0, 0x80, 0xF0, 0x10, 0xF0];
-const DIGIT_6: [u8; 5] = [0xF0, 0x80, 0xF0, 0x90, 0xF0];
-const DIGIT_7: [u8; 5] = [0xF0, 0x10, 0x20, 0x40, 0x40];
-const DIGIT_8: [u8; 5] = [0xF0, 0x90, 0xF0, 0x90, 0xF0];
-const DIGIT_9: [u8; 5] = [0xF0, 0x90, 0xF0, 0x10, 0xF0];
-const DIGIT_A: [u8; 5] = [0xF0, 0x90, 0xF0, 0x90, 0x90];
-const DIGIT_B: [u8; 5] = [0xE0, 0x90, 0xE0, 0x90, 0xE0];
-const DIGIT_C: [u8; 5] = [0xF0, 0x80, 0x80, 0x80, 0xF0];
-const DIGIT_D: [u8; 5] = [0xE0, 0x90, 0x90, 0x90, 0xE0];
-const DIGIT_E: [u8; 5] = [0xF0, 0x80, 0xF0, 0x80, 0xF0];
-const DIGIT_F: [u8; 5] = [0xF0, 0x80, 0xF0, 0x80, 0x80];
-
-pub const FONT_SET: [[u8; 5]; 16] = [DIGIT_0, DIGIT_1, DIGIT_2, DIGIT_3, DIGIT_4, DIGIT_5, DIGIT_6, DIGIT_7, DIGIT_8, DIGIT_9, DIGIT_A, DIGIT_B, DIGIT_C, DIGIT_D, DIGIT_E, DIGIT_F];
\ No newline at end of file
+  fn op_00cn(&mut self, nibble: usize) -> Result<ProgramCounterAction, Chip8Error> { //scroll down n lines
+    self.screen_buffer.scroll_down(nibble, self.planes);
+    self.screen_changed = true;
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_00fb(&mut self) -> Result<ProgramCounterAction, Chip8Error> { //scroll right 4 pixels
+    self.screen_buffer.scroll_right(4, self.planes);
+    self.screen_changed = true;
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_00fc(&mut self) -> Result<ProgramCounterAction, Chip8Error> { //scroll left 4 pixels
+    self.screen_buffer.scroll_left(4, self.planes);
+    self.screen_changed = true;
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_00fd(&mut self) -> Result<ProgramCounterAction, Chip8Error> { //exit the interpreter
+    self.exited = true;
+    Ok(ProgramCounterAction::Jump(self.program_counter))
+  }
+
+  fn op_00fe(&mut self) -> Result<ProgramCounterAction, Chip8Error> { //switch to 64x32
+    self.screen_buffer.resize(CHIP8_WIDTH, CHIP8_HEIGHT);
+    self.screen_changed = true;
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_00ff(&mut self) -> Result<ProgramCounterAction, Chip8Error> { //switch to 128x64
+    self.screen_buffer.resize(SCHIP_WIDTH, SCHIP_HEIGHT);
+    self.screen_changed = true;
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_fx30(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //set i = location of big sprite for digit vx
+    self.i = (self.font_address + SMALL_FONT_SIZE + (self.v[x] as usize & 0xF) * BIG_GLYPH_SIZE) as u16;
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_fx75(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //write v0 to vx to the rpl flags
+    self.flags[..=x].copy_from_slice(&self.v[..=x]);
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_fx85(&mut self, x: usize) -> Result<ProgramCounterAction, Chip8Error> { //read v0 to vx from the rpl flags
+    self.v[..=x].copy_from_slice(&self.flags[..=x]);
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_00dn(&mut self, nibble: usize) -> Result<ProgramCounterAction, Chip8Error> { //scroll up n lines
+    self.screen_buffer.scroll_up(nibble, self.planes);
+    self.screen_changed = true;
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_5xy2(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //write vx to vy to memory starting at i, i is not changed
+    let registers: Vec<usize> = if x <= y { (x..=y).collect() } else { (y..=x).rev().collect() };
+    for (offset, register) in registers.into_iter().enumerate() {
+      self.memory.write(self.i as usize + offset, self.v[register])?;
+    }
+
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_5xy3(&mut self, x: usize, y: usize) -> Result<ProgramCounterAction, Chip8Error> { //read vx to vy from memory starting at i, i is not changed
+    let registers: Vec<usize> = if x <= y { (x..=y).collect() } else { (y..=x).rev().collect() };
+    for (offset, register) in registers.into_iter().enumerate() {
+      self.v[register] = self.memory.read(self.i as usize + offset)?;
+    }
+
+    Ok(ProgramCounterAction::Increment)
+  }
+
+  fn op_f000(&mut self, addr: usize) -> Result<ProgramCounterAction, Chip8Error> { //set i = nnnn
+    self.i = addr as u16;
+    Ok(ProgramCounterAction::Jump(self.program_counter + INSTRUCTION_SIZE * 2))
+  }
+
+  fn op_fn01(&mut self, planes: u8) -> Result<ProgramCounterAction, Chip8Error> { //select the bitplanes to draw on
+    self.planes = planes & 0b11;
+    Ok(ProgramCounterAction::Increment)
+  }
+}
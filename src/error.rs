@@ -0,0 +1,29 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Chip8Error {
+  StackUnderflow { address: usize },
+  StackOverflow { address: usize },
+  ProgramCounterOutOfRange { address: usize },
+  MemoryOutOfRange { address: usize },
+  InvalidKey { key: u8 },
+  UnknownOpcode { opcode: u16, address: usize },
+  RomTooLarge { size: usize },
+}
+
+impl fmt::Display for Chip8Error {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Chip8Error::StackUnderflow { address } => write!(f, "Stack underflow @ {:X}", address),
+      Chip8Error::StackOverflow { address } => write!(f, "Stack overflow @ {:X}", address),
+      Chip8Error::ProgramCounterOutOfRange { address } => write!(f, "Program counter out of range: {:X}", address),
+      Chip8Error::MemoryOutOfRange { address } => write!(f, "Memory access out of range: {:X}", address),
+      Chip8Error::InvalidKey { key } => write!(f, "Invalid key index {:X}", key),
+      Chip8Error::UnknownOpcode { opcode, address } => write!(f, "Unrecognized command {:04X} @ {:X}", opcode, address),
+      Chip8Error::RomTooLarge { size } => write!(f, "ROM of {} bytes does not fit into memory", size),
+    }
+  }
+}
+
+impl Error for Chip8Error {}
@@ -0,0 +1,171 @@
+use std::fs;
+use std::io;
+
+pub const GLYPH_COUNT: usize = 16;
+pub const SMALL_GLYPH_SIZE: usize = 5;
+pub const BIG_GLYPH_SIZE: usize = 10;
+pub const SMALL_FONT_SIZE: usize = GLYPH_COUNT * SMALL_GLYPH_SIZE;
+pub const BIG_FONT_SIZE: usize = GLYPH_COUNT * BIG_GLYPH_SIZE;
+
+// the hex digits fx29 points to, as shipped by the different interpreters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FontSet {
+  CosmacVip,
+  Dream6800,
+  Eti660,
+  Chip48, //also used by super-chip and most modern interpreters
+}
+
+// 16 small 4x5 glyphs followed by 16 big 8x10 glyphs for fx30
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Font {
+  small: Vec<u8>,
+  big: Vec<u8>,
+}
+
+impl Font {
+  pub fn builtin(set: FontSet) -> Font {
+    let glyphs = match set {
+      FontSet::CosmacVip => &COSMAC_VIP_FONT,
+      FontSet::Dream6800 => &DREAM_6800_FONT,
+      FontSet::Eti660 => &ETI_660_FONT,
+      FontSet::Chip48 => &CHIP_48_FONT,
+    };
+
+    Font {
+      small: glyphs.concat(),
+      big: SCHIP_BIG_FONT.concat(),
+    }
+  }
+
+  // raw glyph data, either only the small glyphs or the small glyphs followed by the big ones
+  // without big glyphs the super-chip big font is used
+  pub fn from_bytes(bytes: &[u8]) -> Option<Font> {
+    match bytes.len() {
+      SMALL_FONT_SIZE => Some(Font {
+        small: bytes.to_vec(),
+        big: SCHIP_BIG_FONT.concat(),
+      }),
+      size if size == SMALL_FONT_SIZE + BIG_FONT_SIZE => Some(Font {
+        small: bytes[..SMALL_FONT_SIZE].to_vec(),
+        big: bytes[SMALL_FONT_SIZE..].to_vec(),
+      }),
+      _ => None,
+    }
+  }
+
+  pub fn load(path: &str) -> io::Result<Font> {
+    let bytes = fs::read(path)?;
+    Font::from_bytes(&bytes).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData,
+      format!("font has to be {} or {} bytes, {} has {}", SMALL_FONT_SIZE, SMALL_FONT_SIZE + BIG_FONT_SIZE, path, bytes.len())))
+  }
+
+  pub fn small(&self) -> &[u8] {
+    &self.small
+  }
+
+  pub fn big(&self) -> &[u8] {
+    &self.big
+  }
+}
+
+impl Default for Font {
+  fn default() -> Self {
+    Font::builtin(FontSet::Chip48)
+  }
+}
+
+const COSMAC_VIP_FONT: [[u8; SMALL_GLYPH_SIZE]; GLYPH_COUNT] = [
+  [0xF0, 0x90, 0x90, 0x90, 0xF0], //0
+  [0x60, 0x20, 0x20, 0x20, 0x70], //1
+  [0xF0, 0x10, 0xF0, 0x80, 0xF0], //2
+  [0xF0, 0x10, 0xF0, 0x10, 0xF0], //3
+  [0xA0, 0xA0, 0xF0, 0x20, 0x20], //4
+  [0xF0, 0x80, 0xF0, 0x10, 0xF0], //5
+  [0xF0, 0x80, 0xF0, 0x90, 0xF0], //6
+  [0xF0, 0x10, 0x10, 0x10, 0x10], //7
+  [0xF0, 0x90, 0xF0, 0x90, 0xF0], //8
+  [0xF0, 0x90, 0xF0, 0x10, 0xF0], //9
+  [0xF0, 0x90, 0xF0, 0x90, 0x90], //A
+  [0xF0, 0x50, 0x70, 0x50, 0xF0], //B
+  [0xF0, 0x80, 0x80, 0x80, 0xF0], //C
+  [0xF0, 0x50, 0x50, 0x50, 0xF0], //D
+  [0xF0, 0x80, 0xF0, 0x80, 0xF0], //E
+  [0xF0, 0x80, 0xF0, 0x80, 0x80], //F
+];
+
+const DREAM_6800_FONT: [[u8; SMALL_GLYPH_SIZE]; GLYPH_COUNT] = [
+  [0xE0, 0xA0, 0xA0, 0xA0, 0xE0], //0
+  [0x40, 0x40, 0x40, 0x40, 0x40], //1
+  [0xE0, 0x20, 0xE0, 0x80, 0xE0], //2
+  [0xE0, 0x20, 0xE0, 0x20, 0xE0], //3
+  [0x80, 0xA0, 0xA0, 0xE0, 0x20], //4
+  [0xE0, 0x80, 0xE0, 0x20, 0xE0], //5
+  [0xE0, 0x80, 0xE0, 0xA0, 0xE0], //6
+  [0xE0, 0x20, 0x20, 0x20, 0x20], //7
+  [0xE0, 0xA0, 0xE0, 0xA0, 0xE0], //8
+  [0xE0, 0xA0, 0xE0, 0x20, 0xE0], //9
+  [0xE0, 0xA0, 0xE0, 0xA0, 0xA0], //A
+  [0xC0, 0xA0, 0xE0, 0xA0, 0xC0], //B
+  [0xE0, 0x80, 0x80, 0x80, 0xE0], //C
+  [0xC0, 0xA0, 0xA0, 0xA0, 0xC0], //D
+  [0xE0, 0x80, 0xE0, 0x80, 0xE0], //E
+  [0xE0, 0x80, 0xC0, 0x80, 0x80], //F
+];
+
+const ETI_660_FONT: [[u8; SMALL_GLYPH_SIZE]; GLYPH_COUNT] = [
+  [0xE0, 0xA0, 0xA0, 0xA0, 0xE0], //0
+  [0x20, 0x20, 0x20, 0x20, 0x20], //1
+  [0xE0, 0x20, 0xE0, 0x80, 0xE0], //2
+  [0xE0, 0x20, 0xE0, 0x20, 0xE0], //3
+  [0xA0, 0xA0, 0xE0, 0x20, 0x20], //4
+  [0xE0, 0x80, 0xE0, 0x20, 0xE0], //5
+  [0xE0, 0x80, 0xE0, 0xA0, 0xE0], //6
+  [0xE0, 0x20, 0x20, 0x20, 0x20], //7
+  [0xE0, 0xA0, 0xE0, 0xA0, 0xE0], //8
+  [0xE0, 0xA0, 0xE0, 0x20, 0xE0], //9
+  [0xE0, 0xA0, 0xE0, 0xA0, 0xA0], //A
+  [0x80, 0x80, 0xE0, 0xA0, 0xE0], //B
+  [0xE0, 0x80, 0x80, 0x80, 0xE0], //C
+  [0x20, 0x20, 0xE0, 0xA0, 0xE0], //D
+  [0xE0, 0x80, 0xE0, 0x80, 0xE0], //E
+  [0xE0, 0x80, 0xC0, 0x80, 0x80], //F
+];
+
+const CHIP_48_FONT: [[u8; SMALL_GLYPH_SIZE]; GLYPH_COUNT] = [
+  [0xF0, 0x90, 0x90, 0x90, 0xF0], //0
+  [0x20, 0x60, 0x20, 0x20, 0x70], //1
+  [0xF0, 0x10, 0xF0, 0x80, 0xF0], //2
+  [0xF0, 0x10, 0xF0, 0x10, 0xF0], //3
+  [0x90, 0x90, 0xF0, 0x10, 0x10], //4
+  [0xF0, 0x80, 0xF0, 0x10, 0xF0], //5
+  [0xF0, 0x80, 0xF0, 0x90, 0xF0], //6
+  [0xF0, 0x10, 0x20, 0x40, 0x40], //7
+  [0xF0, 0x90, 0xF0, 0x90, 0xF0], //8
+  [0xF0, 0x90, 0xF0, 0x10, 0xF0], //9
+  [0xF0, 0x90, 0xF0, 0x90, 0x90], //A
+  [0xE0, 0x90, 0xE0, 0x90, 0xE0], //B
+  [0xF0, 0x80, 0x80, 0x80, 0xF0], //C
+  [0xE0, 0x90, 0x90, 0x90, 0xE0], //D
+  [0xF0, 0x80, 0xF0, 0x80, 0xF0], //E
+  [0xF0, 0x80, 0xF0, 0x80, 0x80], //F
+];
+// super-chip 1.1 only has the digits, the letters follow octo
+const SCHIP_BIG_FONT: [[u8; BIG_GLYPH_SIZE]; GLYPH_COUNT] = [
+  [0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C], //0
+  [0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C], //1
+  [0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF], //2
+  [0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C], //3
+  [0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06], //4
+  [0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C], //5
+  [0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C], //6
+  [0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60], //7
+  [0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C], //8
+  [0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C], //9
+  [0x18, 0x3C, 0x66, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3], //A
+  [0xFC, 0xFE, 0xC3, 0xC3, 0xFE, 0xFE, 0xC3, 0xC3, 0xFE, 0xFC], //B
+  [0x3C, 0x7E, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0x7E, 0x3C], //C
+  [0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC], //D
+  [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF], //E
+  [0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0], //F
+];
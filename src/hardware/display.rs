@@ -6,10 +6,19 @@ use sdl2::video::Window;
 
 use crate::cpu::CHIP8_HEIGHT;
 use crate::cpu::CHIP8_WIDTH;
+use crate::screen::Screen;
 
 const WINDOW_WIDTH: u32 = 960;
 const WINDOW_HEIGHT: u32 = 480;
 
+//background, plane 1, plane 2, both planes
+const PALETTE: [Color; 4] = [
+  Color { r: 0, g: 0, b: 0, a: 0xFF },
+  Color { r: 80, g: 255, b: 80, a: 0xFF },
+  Color { r: 30, g: 110, b: 30, a: 0xFF },
+  Color { r: 200, g: 255, b: 200, a: 0xFF },
+];
+
 pub struct Display {
   canvas: Canvas<Window>,
 }
@@ -35,15 +44,22 @@ impl Display {
     }
   }
 
-  pub fn draw_screen(&mut self, screen_buffer: &[[bool; CHIP8_WIDTH]; CHIP8_HEIGHT]) {
-    self.canvas.set_draw_color(Color::RGB(0, 0, 0));
+  pub fn draw_screen(&mut self, screen_buffer: &Screen) {
+    let logical_size = (screen_buffer.width() as u32, screen_buffer.height() as u32);
+    if self.canvas.logical_size() != logical_size {
+      self.canvas.set_logical_size(logical_size.0, logical_size.1).unwrap();
+    }
+
+    self.canvas.set_draw_color(PALETTE[0]);
     self.canvas.clear();
 
-    self.canvas.set_draw_color(Color::RGB(80, 255, 80));
-    for (y, line) in screen_buffer.iter().enumerate() {
-      for (x, pixel) in line.iter().enumerate() {
-        if *pixel {
-          self.canvas.draw_point(Point::new(x as i32, y as i32)).unwrap();
+    for (color, palette_color) in PALETTE.iter().enumerate().skip(1) {
+      self.canvas.set_draw_color(*palette_color);
+      for (y, line) in screen_buffer.rows().enumerate() {
+        for (x, pixel) in line.iter().enumerate() {
+          if *pixel as usize == color {
+            self.canvas.draw_point(Point::new(x as i32, y as i32)).unwrap();
+          }
         }
       }
     }
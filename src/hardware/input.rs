@@ -3,9 +3,24 @@ use sdl2::EventPump;
 use sdl2::keyboard::Keycode;
 use sdl2::Sdl;
 
+pub enum Hotkey {
+  SpeedUp,
+  SpeedDown,
+  SelectSlot(u8),
+  SaveState,
+  LoadState,
+}
+
+pub struct InputState {
+  pub keys: [bool; 16],
+  pub hotkeys: Vec<Hotkey>,
+  pub rewind: bool,
+}
+
 pub struct Input {
   event_pump: EventPump,
   keys: [bool; 16],
+  rewind: bool,
 }
 
 impl Input {
@@ -13,6 +28,7 @@ impl Input {
     Input {
       event_pump: sdl.event_pump().unwrap(),
       keys: [false; 16],
+      rewind: false,
     }
   }
 
@@ -20,8 +36,14 @@ impl Input {
   // 4 5 6 D    Q W E R
   // 7 8 9 E    A S D F
   // A 0 B F    Y X C V
+  //
+  // PageUp/PageDown change the emulation speed
+  // F1-F4 select the save state slot, F5 saves and F9 loads it
+  // holding Backspace rewinds
+
+  pub fn process_input(&mut self) -> Result<InputState, &str> {
+    let mut hotkeys = Vec::new();
 
-  pub fn process_input(&mut self) -> Result<[bool; 16], &str> {
     for event in self.event_pump.poll_iter() {
       match event {
         Event::Quit { .. } | Event::KeyDown { keycode: Some(Keycode::Escape), .. } => return Err("Esc"),
@@ -57,10 +79,24 @@ impl Input {
         Event::KeyUp { keycode: Some(Keycode::F), .. } => self.keys[14] = false,
         Event::KeyDown { keycode: Some(Keycode::V), .. } => self.keys[15] = true,
         Event::KeyUp { keycode: Some(Keycode::V), .. } => self.keys[15] = false,
+        Event::KeyDown { keycode: Some(Keycode::PageUp), .. } => hotkeys.push(Hotkey::SpeedUp),
+        Event::KeyDown { keycode: Some(Keycode::PageDown), .. } => hotkeys.push(Hotkey::SpeedDown),
+        Event::KeyDown { keycode: Some(Keycode::F1), .. } => hotkeys.push(Hotkey::SelectSlot(1)),
+        Event::KeyDown { keycode: Some(Keycode::F2), .. } => hotkeys.push(Hotkey::SelectSlot(2)),
+        Event::KeyDown { keycode: Some(Keycode::F3), .. } => hotkeys.push(Hotkey::SelectSlot(3)),
+        Event::KeyDown { keycode: Some(Keycode::F4), .. } => hotkeys.push(Hotkey::SelectSlot(4)),
+        Event::KeyDown { keycode: Some(Keycode::F5), .. } => hotkeys.push(Hotkey::SaveState),
+        Event::KeyDown { keycode: Some(Keycode::F9), .. } => hotkeys.push(Hotkey::LoadState),
+        Event::KeyDown { keycode: Some(Keycode::Backspace), .. } => self.rewind = true,
+        Event::KeyUp { keycode: Some(Keycode::Backspace), .. } => self.rewind = false,
         _ => {}
       }
     }
 
-    Ok(self.keys.clone())
+    Ok(InputState {
+      keys: self.keys,
+      hotkeys,
+      rewind: self.rewind,
+    })
   }
 }
\ No newline at end of file
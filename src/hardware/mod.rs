@@ -1,4 +1,3 @@
 pub mod display;
-pub mod rom;
 pub mod input;
-pub mod sound;
\ No newline at end of file
+pub mod sound;
@@ -37,9 +37,8 @@ impl Sound {
   }
 
   pub fn stop(&mut self) {
-    match self.device.status() {
-      AudioStatus::Playing => self.device.pause(),
-      _ => ()
+    if self.device.status() == AudioStatus::Playing {
+      self.device.pause();
     }
   }
 }
@@ -0,0 +1,413 @@
+use std::error::Error;
+use std::fmt;
+
+// x and y are register indices, addresses are 12 bit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+  Sys(u16),                     //0nnn - system routine, ignored
+  ClearScreen,                  //00e0
+  Return,                       //00ee
+  Jump(u16),                    //1nnn
+  Call(u16),                    //2nnn
+  SkipEqualByte(u8, u8),        //3xkk
+  SkipNotEqualByte(u8, u8),     //4xkk
+  SkipEqual(u8, u8),            //5xy0
+  LoadByte(u8, u8),             //6xkk
+  AddByte(u8, u8),              //7xkk
+  Load(u8, u8),                 //8xy0
+  Or(u8, u8),                   //8xy1
+  And(u8, u8),                  //8xy2
+  Xor(u8, u8),                  //8xy3
+  Add(u8, u8),                  //8xy4
+  Sub(u8, u8),                  //8xy5
+  ShiftRight(u8, u8),           //8xy6
+  SubReverse(u8, u8),           //8xy7
+  ShiftLeft(u8, u8),            //8xye
+  SkipNotEqual(u8, u8),         //9xy0
+  LoadI(u16),                   //annn
+  JumpOffset(u16),              //bnnn
+  Random(u8, u8),               //cxkk
+  Draw(u8, u8, u8),             //dxyn
+  SkipKeyPressed(u8),           //ex9e
+  SkipKeyNotPressed(u8),        //exa1
+  LoadDelayTimer(u8),           //fx07
+  WaitForKey(u8),               //fx0a
+  SetDelayTimer(u8),            //fx15
+  SetSoundTimer(u8),            //fx18
+  AddI(u8),                     //fx1e
+  LoadFont(u8),                 //fx29
+  StoreBcd(u8),                 //fx33
+  StoreRegisters(u8),           //fx55
+  LoadRegisters(u8),            //fx65
+
+  //super-chip
+  ScrollDown(u8),               //00cn
+  ScrollRight,                  //00fb
+  ScrollLeft,                   //00fc
+  Exit,                         //00fd
+  LowResolution,                //00fe
+  HighResolution,               //00ff
+  LoadBigFont(u8),              //fx30
+  StoreFlags(u8),               //fx75
+  LoadFlags(u8),                //fx85
+
+  //xo-chip
+  ScrollUp(u8),                 //00dn
+  SaveRange(u8, u8),            //5xy2
+  LoadRange(u8, u8),            //5xy3
+  LoadLongI(u16),               //f000 nnnn - the only 4 byte instruction
+  SelectPlanes(u8),             //fn01
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownOpcode(pub u16);
+
+impl fmt::Display for UnknownOpcode {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "Unrecognized command {:04X}", self.0)
+  }
+}
+
+impl Error for UnknownOpcode {}
+
+impl Instruction {
+  pub fn decode(op: u16) -> Result<Instruction, UnknownOpcode> {
+    let half_bytes = (
+      ((op & 0xF000) >> 12) as u8,
+      ((op & 0x0F00) >> 8) as u8,
+      ((op & 0x00F0) >> 4) as u8,
+      (op & 0x000F) as u8
+    );
+
+    let addr = op & 0x0FFF;
+    let byte = (op & 0x00FF) as u8;
+
+    let x = half_bytes.1;
+    let y = half_bytes.2;
+    let nibble = half_bytes.3;
+
+    let instruction = match half_bytes {
+      (0x0, 0x0, 0xE, 0x0) => Instruction::ClearScreen,
+      (0x0, 0x0, 0xE, 0xE) => Instruction::Return,
+      (0x0, 0x0, 0xC,   _) => Instruction::ScrollDown(nibble),
+      (0x0, 0x0, 0xD,   _) => Instruction::ScrollUp(nibble),
+      (0x0, 0x0, 0xF, 0xB) => Instruction::ScrollRight,
+      (0x0, 0x0, 0xF, 0xC) => Instruction::ScrollLeft,
+      (0x0, 0x0, 0xF, 0xD) => Instruction::Exit,
+      (0x0, 0x0, 0xF, 0xE) => Instruction::LowResolution,
+      (0x0, 0x0, 0xF, 0xF) => Instruction::HighResolution,
+      (0x0,   _,   _,   _) => Instruction::Sys(addr),
+      (0x1,   _,   _,   _) => Instruction::Jump(addr),
+      (0x2,   _,   _,   _) => Instruction::Call(addr),
+      (0x3,   _,   _,   _) => Instruction::SkipEqualByte(x, byte),
+      (0x4,   _,   _,   _) => Instruction::SkipNotEqualByte(x, byte),
+      (0x5,   _,   _, 0x0) => Instruction::SkipEqual(x, y),
+      (0x5,   _,   _, 0x2) => Instruction::SaveRange(x, y),
+      (0x5,   _,   _, 0x3) => Instruction::LoadRange(x, y),
+      (0x6,   _,   _,   _) => Instruction::LoadByte(x, byte),
+      (0x7,   _,   _,   _) => Instruction::AddByte(x, byte),
+      (0x8,   _,   _, 0x0) => Instruction::Load(x, y),
+      (0x8,   _,   _, 0x1) => Instruction::Or(x, y),
+      (0x8,   _,   _, 0x2) => Instruction::And(x, y),
+      (0x8,   _,   _, 0x3) => Instruction::Xor(x, y),
+      (0x8,   _,   _, 0x4) => Instruction::Add(x, y),
+      (0x8,   _,   _, 0x5) => Instruction::Sub(x, y),
+      (0x8,   _,   _, 0x6) => Instruction::ShiftRight(x, y),
+      (0x8,   _,   _, 0x7) => Instruction::SubReverse(x, y),
+      (0x8,   _,   _, 0xE) => Instruction::ShiftLeft(x, y),
+      (0x9,   _,   _, 0x0) => Instruction::SkipNotEqual(x, y),
+      (0xA,   _,   _,   _) => Instruction::LoadI(addr),
+      (0xB,   _,   _,   _) => Instruction::JumpOffset(addr),
+      (0xC,   _,   _,   _) => Instruction::Random(x, byte),
+      (0xD,   _,   _,   _) => Instruction::Draw(x, y, nibble),
+      (0xE,   _, 0x9, 0xE) => Instruction::SkipKeyPressed(x),
+      (0xE,   _, 0xA, 0x1) => Instruction::SkipKeyNotPressed(x),
+      (0xF, 0x0, 0x0, 0x0) => Instruction::LoadLongI(0), //the address is in the next word, see decode_long
+      (0xF,   _, 0x0, 0x1) => Instruction::SelectPlanes(x),
+      (0xF,   _, 0x0, 0x7) => Instruction::LoadDelayTimer(x),
+      (0xF,   _, 0x0, 0xA) => Instruction::WaitForKey(x),
+      (0xF,   _, 0x1, 0x5) => Instruction::SetDelayTimer(x),
+      (0xF,   _, 0x1, 0x8) => Instruction::SetSoundTimer(x),
+      (0xF,   _, 0x1, 0xE) => Instruction::AddI(x),
+      (0xF,   _, 0x2, 0x9) => Instruction::LoadFont(x),
+      (0xF,   _, 0x3, 0x3) => Instruction::StoreBcd(x),
+      (0xF,   _, 0x5, 0x5) => Instruction::StoreRegisters(x),
+      (0xF,   _, 0x6, 0x5) => Instruction::LoadRegisters(x),
+      (0xF,   _, 0x3, 0x0) => Instruction::LoadBigFont(x),
+      (0xF,   _, 0x7, 0x5) => Instruction::StoreFlags(x),
+      (0xF,   _, 0x8, 0x5) => Instruction::LoadFlags(x),
+      (  _,   _,   _,   _) => return Err(UnknownOpcode(op)),
+    };
+
+    Ok(instruction)
+  }
+
+  // decodes an instruction together with the word following it, which is only used by f000 nnnn
+  pub fn decode_long(op: u16, next: u16) -> Result<Instruction, UnknownOpcode> {
+    match Instruction::decode(op)? {
+      Instruction::LoadLongI(_) => Ok(Instruction::LoadLongI(next)),
+      instruction => Ok(instruction),
+    }
+  }
+
+  // size in bytes
+  pub fn size(&self) -> usize {
+    match *self {
+      Instruction::LoadLongI(_) => 4,
+      _ => 2
+    }
+  }
+
+  // the second word of a 4 byte instruction
+  pub fn operand(&self) -> Option<u16> {
+    match *self {
+      Instruction::LoadLongI(addr) => Some(addr),
+      _ => None
+    }
+  }
+
+  // the opcode with its operands replaced by placeholders, like DXYN, groups instructions for statistics
+  pub fn pattern(&self) -> &'static str {
+    match *self {
+      Instruction::Sys(..) => "0NNN",
+      Instruction::ClearScreen => "00E0",
+      Instruction::Return => "00EE",
+      Instruction::Jump(..) => "1NNN",
+      Instruction::Call(..) => "2NNN",
+      Instruction::SkipEqualByte(..) => "3XNN",
+      Instruction::SkipNotEqualByte(..) => "4XNN",
+      Instruction::SkipEqual(..) => "5XY0",
+      Instruction::LoadByte(..) => "6XNN",
+      Instruction::AddByte(..) => "7XNN",
+      Instruction::Load(..) => "8XY0",
+      Instruction::Or(..) => "8XY1",
+      Instruction::And(..) => "8XY2",
+      Instruction::Xor(..) => "8XY3",
+      Instruction::Add(..) => "8XY4",
+      Instruction::Sub(..) => "8XY5",
+      Instruction::ShiftRight(..) => "8XY6",
+      Instruction::SubReverse(..) => "8XY7",
+      Instruction::ShiftLeft(..) => "8XYE",
+      Instruction::SkipNotEqual(..) => "9XY0",
+      Instruction::LoadI(..) => "ANNN",
+      Instruction::JumpOffset(..) => "BNNN",
+      Instruction::Random(..) => "CXNN",
+      Instruction::Draw(..) => "DXYN",
+      Instruction::SkipKeyPressed(..) => "EX9E",
+      Instruction::SkipKeyNotPressed(..) => "EXA1",
+      Instruction::LoadDelayTimer(..) => "FX07",
+      Instruction::WaitForKey(..) => "FX0A",
+      Instruction::SetDelayTimer(..) => "FX15",
+      Instruction::SetSoundTimer(..) => "FX18",
+      Instruction::AddI(..) => "FX1E",
+      Instruction::LoadFont(..) => "FX29",
+      Instruction::StoreBcd(..) => "FX33",
+      Instruction::StoreRegisters(..) => "FX55",
+      Instruction::LoadRegisters(..) => "FX65",
+      Instruction::ScrollDown(..) => "00CN",
+      Instruction::ScrollRight => "00FB",
+      Instruction::ScrollLeft => "00FC",
+      Instruction::Exit => "00FD",
+      Instruction::LowResolution => "00FE",
+      Instruction::HighResolution => "00FF",
+      Instruction::LoadBigFont(..) => "FX30",
+      Instruction::StoreFlags(..) => "FX75",
+      Instruction::LoadFlags(..) => "FX85",
+      Instruction::ScrollUp(..) => "00DN",
+      Instruction::SaveRange(..) => "5XY2",
+      Instruction::LoadRange(..) => "5XY3",
+      Instruction::LoadLongI(..) => "F000",
+      Instruction::SelectPlanes(..) => "FN01",
+    }
+  }
+
+  // instructions that only exist in the super-chip instruction set
+  pub fn is_superchip(&self) -> bool {
+    matches!(
+      *self,
+      Instruction::ScrollDown(_) | Instruction::ScrollRight | Instruction::ScrollLeft | Instruction::Exit |
+      Instruction::LowResolution | Instruction::HighResolution | Instruction::LoadBigFont(_) |
+      Instruction::StoreFlags(_) | Instruction::LoadFlags(_)
+    )
+  }
+
+  // the scroll instructions super-chip 1.1 added to 1.0
+  pub fn is_superchip11(&self) -> bool {
+    matches!(*self, Instruction::ScrollDown(_) | Instruction::ScrollRight | Instruction::ScrollLeft)
+  }
+
+  // instructions that only exist in the xo-chip instruction set
+  pub fn is_xochip(&self) -> bool {
+    matches!(
+      *self,
+      Instruction::ScrollUp(_) | Instruction::SaveRange(_, _) | Instruction::LoadRange(_, _) |
+      Instruction::LoadLongI(_) | Instruction::SelectPlanes(_)
+    )
+  }
+
+  // first word of the instruction, see operand for the second word of f000 nnnn
+  pub fn encode(&self) -> u16 {
+    let xy = |prefix: u16, x: u8, y: u8, suffix: u16| prefix << 12 | (x as u16) << 8 | (y as u16) << 4 | suffix;
+    let xkk = |prefix: u16, x: u8, byte: u8| prefix << 12 | (x as u16) << 8 | byte as u16;
+    let fx = |x: u8, suffix: u16| 0xF000 | (x as u16) << 8 | suffix;
+
+    match *self {
+      Instruction::Sys(addr) => addr & 0x0FFF,
+      Instruction::ClearScreen => 0x00E0,
+      Instruction::Return => 0x00EE,
+      Instruction::Jump(addr) => 0x1000 | (addr & 0x0FFF),
+      Instruction::Call(addr) => 0x2000 | (addr & 0x0FFF),
+      Instruction::SkipEqualByte(x, byte) => xkk(0x3, x, byte),
+      Instruction::SkipNotEqualByte(x, byte) => xkk(0x4, x, byte),
+      Instruction::SkipEqual(x, y) => xy(0x5, x, y, 0x0),
+      Instruction::LoadByte(x, byte) => xkk(0x6, x, byte),
+      Instruction::AddByte(x, byte) => xkk(0x7, x, byte),
+      Instruction::Load(x, y) => xy(0x8, x, y, 0x0),
+      Instruction::Or(x, y) => xy(0x8, x, y, 0x1),
+      Instruction::And(x, y) => xy(0x8, x, y, 0x2),
+      Instruction::Xor(x, y) => xy(0x8, x, y, 0x3),
+      Instruction::Add(x, y) => xy(0x8, x, y, 0x4),
+      Instruction::Sub(x, y) => xy(0x8, x, y, 0x5),
+      Instruction::ShiftRight(x, y) => xy(0x8, x, y, 0x6),
+      Instruction::SubReverse(x, y) => xy(0x8, x, y, 0x7),
+      Instruction::ShiftLeft(x, y) => xy(0x8, x, y, 0xE),
+      Instruction::SkipNotEqual(x, y) => xy(0x9, x, y, 0x0),
+      Instruction::LoadI(addr) => 0xA000 | (addr & 0x0FFF),
+      Instruction::JumpOffset(addr) => 0xB000 | (addr & 0x0FFF),
+      Instruction::Random(x, byte) => xkk(0xC, x, byte),
+      Instruction::Draw(x, y, nibble) => xy(0xD, x, y, nibble as u16 & 0xF),
+      Instruction::SkipKeyPressed(x) => xkk(0xE, x, 0x9E),
+      Instruction::SkipKeyNotPressed(x) => xkk(0xE, x, 0xA1),
+      Instruction::LoadDelayTimer(x) => fx(x, 0x07),
+      Instruction::WaitForKey(x) => fx(x, 0x0A),
+      Instruction::SetDelayTimer(x) => fx(x, 0x15),
+      Instruction::SetSoundTimer(x) => fx(x, 0x18),
+      Instruction::AddI(x) => fx(x, 0x1E),
+      Instruction::LoadFont(x) => fx(x, 0x29),
+      Instruction::StoreBcd(x) => fx(x, 0x33),
+      Instruction::StoreRegisters(x) => fx(x, 0x55),
+      Instruction::LoadRegisters(x) => fx(x, 0x65),
+      Instruction::ScrollDown(nibble) => 0x00C0 | (nibble as u16 & 0xF),
+      Instruction::ScrollRight => 0x00FB,
+      Instruction::ScrollLeft => 0x00FC,
+      Instruction::Exit => 0x00FD,
+      Instruction::LowResolution => 0x00FE,
+      Instruction::HighResolution => 0x00FF,
+      Instruction::LoadBigFont(x) => fx(x, 0x30),
+      Instruction::StoreFlags(x) => fx(x, 0x75),
+      Instruction::LoadFlags(x) => fx(x, 0x85),
+      Instruction::ScrollUp(nibble) => 0x00D0 | (nibble as u16 & 0xF),
+      Instruction::SaveRange(x, y) => xy(0x5, x, y, 0x2),
+      Instruction::LoadRange(x, y) => xy(0x5, x, y, 0x3),
+      Instruction::LoadLongI(_) => 0xF000,
+      Instruction::SelectPlanes(planes) => fx(planes, 0x01),
+    }
+  }
+}
+
+// mnemonics follow Cowgod's Chip-8 technical reference
+impl fmt::Display for Instruction {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Instruction::Sys(addr) => write!(f, "SYS 0x{:03X}", addr),
+      Instruction::ClearScreen => write!(f, "CLS"),
+      Instruction::Return => write!(f, "RET"),
+      Instruction::Jump(addr) => write!(f, "JP 0x{:03X}", addr),
+      Instruction::Call(addr) => write!(f, "CALL 0x{:03X}", addr),
+      Instruction::SkipEqualByte(x, byte) => write!(f, "SE V{:X}, 0x{:02X}", x, byte),
+      Instruction::SkipNotEqualByte(x, byte) => write!(f, "SNE V{:X}, 0x{:02X}", x, byte),
+      Instruction::SkipEqual(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+      Instruction::LoadByte(x, byte) => write!(f, "LD V{:X}, 0x{:02X}", x, byte),
+      Instruction::AddByte(x, byte) => write!(f, "ADD V{:X}, 0x{:02X}", x, byte),
+      Instruction::Load(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+      Instruction::Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+      Instruction::And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+      Instruction::Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+      Instruction::Add(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+      Instruction::Sub(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+      Instruction::ShiftRight(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+      Instruction::SubReverse(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+      Instruction::ShiftLeft(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+      Instruction::SkipNotEqual(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+      Instruction::LoadI(addr) => write!(f, "LD I, 0x{:03X}", addr),
+      Instruction::JumpOffset(addr) => write!(f, "JP V0, 0x{:03X}", addr),
+      Instruction::Random(x, byte) => write!(f, "RND V{:X}, 0x{:02X}", x, byte),
+      Instruction::Draw(x, y, nibble) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, nibble),
+      Instruction::SkipKeyPressed(x) => write!(f, "SKP V{:X}", x),
+      Instruction::SkipKeyNotPressed(x) => write!(f, "SKNP V{:X}", x),
+      Instruction::LoadDelayTimer(x) => write!(f, "LD V{:X}, DT", x),
+      Instruction::WaitForKey(x) => write!(f, "LD V{:X}, K", x),
+      Instruction::SetDelayTimer(x) => write!(f, "LD DT, V{:X}", x),
+      Instruction::SetSoundTimer(x) => write!(f, "LD ST, V{:X}", x),
+      Instruction::AddI(x) => write!(f, "ADD I, V{:X}", x),
+      Instruction::LoadFont(x) => write!(f, "LD F, V{:X}", x),
+      Instruction::StoreBcd(x) => write!(f, "LD B, V{:X}", x),
+      Instruction::StoreRegisters(x) => write!(f, "LD [I], V{:X}", x),
+      Instruction::LoadRegisters(x) => write!(f, "LD V{:X}, [I]", x),
+      Instruction::ScrollDown(nibble) => write!(f, "SCD {}", nibble),
+      Instruction::ScrollRight => write!(f, "SCR"),
+      Instruction::ScrollLeft => write!(f, "SCL"),
+      Instruction::Exit => write!(f, "EXIT"),
+      Instruction::LowResolution => write!(f, "LOW"),
+      Instruction::HighResolution => write!(f, "HIGH"),
+      Instruction::LoadBigFont(x) => write!(f, "LD HF, V{:X}", x),
+      Instruction::StoreFlags(x) => write!(f, "LD R, V{:X}", x),
+      Instruction::LoadFlags(x) => write!(f, "LD V{:X}, R", x),
+      Instruction::ScrollUp(nibble) => write!(f, "SCU {}", nibble),
+      Instruction::SaveRange(x, y) => write!(f, "SAVE V{:X} - V{:X}", x, y),
+      Instruction::LoadRange(x, y) => write!(f, "LOAD V{:X} - V{:X}", x, y),
+      Instruction::LoadLongI(addr) => write!(f, "LD I, LONG 0x{:04X}", addr),
+      Instruction::SelectPlanes(planes) => write!(f, "PLANE {}", planes),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn every_opcode_round_trips() {
+    for op in 0..=0xFFFF {
+      match Instruction::decode(op) {
+        Ok(instruction) => {
+          assert_eq!(instruction.encode(), op, "{:04X} decoded to {:?}", op, instruction);
+          assert!(!instruction.to_string().is_empty());
+        }
+        Err(error) => assert_eq!(error, UnknownOpcode(op)),
+      }
+    }
+  }
+
+  #[test]
+  fn long_load_keeps_its_operand() {
+    let instruction = Instruction::decode_long(0xF000, 0x1234).unwrap();
+    assert_eq!(instruction, Instruction::LoadLongI(0x1234));
+    assert_eq!((instruction.encode(), instruction.operand(), instruction.size()), (0xF000, Some(0x1234), 4));
+
+    assert_eq!(Instruction::decode_long(0x6A05, 0xF000), Ok(Instruction::LoadByte(0xA, 0x05)));
+  }
+
+  #[test]
+  fn unknown_opcodes() {
+    for op in [0x5001, 0x800F, 0x9001, 0xE000, 0xF0FF].iter() {
+      assert_eq!(Instruction::decode(*op), Err(UnknownOpcode(*op)));
+    }
+  }
+
+  #[test]
+  fn mnemonics() {
+    let mnemonic = |op| Instruction::decode(op).unwrap().to_string();
+    assert_eq!(mnemonic(0x00E0), "CLS");
+    assert_eq!(mnemonic(0x0123), "SYS 0x123");
+    assert_eq!(mnemonic(0x2ABC), "CALL 0xABC");
+    assert_eq!(mnemonic(0x3A0F), "SE VA, 0x0F");
+    assert_eq!(mnemonic(0x8DE7), "SUBN VD, VE");
+    assert_eq!(mnemonic(0xD125), "DRW V1, V2, 5");
+    assert_eq!(mnemonic(0xF40A), "LD V4, K");
+    assert_eq!(mnemonic(0xFB65), "LD VB, [I]");
+    assert_eq!(mnemonic(0x00C3), "SCD 3");
+    assert_eq!(mnemonic(0x5123), "LOAD V1 - V2");
+    assert_eq!(Instruction::LoadLongI(0xBEEF).to_string(), "LD I, LONG 0xBEEF");
+  }
+}
@@ -0,0 +1,180 @@
+pub const KEY_COUNT: usize = 16;
+
+// the hex keypad, besides the current state it remembers which keys went down or up
+// between input updates until the interpreter consumes those edges
+#[derive(Clone)]
+pub struct Keypad {
+  keys: [bool; KEY_COUNT],
+  pressed: [bool; KEY_COUNT],
+  released: [bool; KEY_COUNT],
+}
+
+impl Keypad {
+  pub fn new() -> Keypad {
+    Keypad {
+      keys: [false; KEY_COUNT],
+      pressed: [false; KEY_COUNT],
+      released: [false; KEY_COUNT],
+    }
+  }
+
+  pub fn keys(&self) -> [bool; KEY_COUNT] {
+    self.keys
+  }
+
+  pub fn set(&mut self, keys: [bool; KEY_COUNT]) {
+    for (key, down) in keys.iter().enumerate() {
+      self.pressed[key] |= *down && !self.keys[key];
+      self.released[key] |= !*down && self.keys[key];
+    }
+    self.keys = keys;
+  }
+
+  pub fn is_down(&self, key: usize) -> Option<bool> {
+    self.keys.get(key).copied()
+  }
+
+  // forgets all edges seen so far, only presses after this call count
+  pub fn clear_edges(&mut self) {
+    self.pressed = [false; KEY_COUNT];
+    self.released = [false; KEY_COUNT];
+  }
+
+  // the lowest key that went down since the edges were cleared
+  pub fn take_press(&mut self) -> Option<u8> {
+    let key = self.pressed.iter().position(|pressed| *pressed)?;
+    self.pressed[key] = false;
+    if self.keys[key] {
+      self.released[key] = false; //a release seen while the key is down again happened before this press
+    }
+    Some(key as u8)
+  }
+
+  pub fn take_release(&mut self, key: u8) -> bool {
+    let key = key as usize;
+    std::mem::replace(&mut self.released[key], false)
+  }
+
+  // keys, pressed and released edges packed into 16 bit masks
+  pub fn to_bits(&self) -> [u16; 3] {
+    [pack(&self.keys), pack(&self.pressed), pack(&self.released)]
+  }
+
+  pub fn from_bits(bits: [u16; 3]) -> Keypad {
+    Keypad {
+      keys: unpack(bits[0]),
+      pressed: unpack(bits[1]),
+      released: unpack(bits[2]),
+    }
+  }
+}
+
+impl Default for Keypad {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn pack(keys: &[bool; KEY_COUNT]) -> u16 {
+  keys.iter().enumerate().fold(0, |bits, (key, down)| bits | (*down as u16) << key)
+}
+
+fn unpack(bits: u16) -> [bool; KEY_COUNT] {
+  let mut keys = [false; KEY_COUNT];
+  for (key, down) in keys.iter_mut().enumerate() {
+    *down = bits & 1 << key != 0;
+  }
+  keys
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn keys(down: &[usize]) -> [bool; KEY_COUNT] {
+    let mut keys = [false; KEY_COUNT];
+    for key in down {
+      keys[*key] = true;
+    }
+    keys
+  }
+
+  // what the interpreter does on every step while fx0a waits
+  fn wait(keypad: &mut Keypad, input_key: &mut Option<u8>) -> Option<u8> {
+    if input_key.is_none() {
+      *input_key = keypad.take_press();
+    }
+    let key = (*input_key)?;
+    if keypad.take_release(key) {
+      input_key.take()
+    } else {
+      None
+    }
+  }
+
+  #[test]
+  fn press_and_release_between_steps() {
+    let mut keypad = Keypad::new();
+    let mut input_key = None;
+    keypad.clear_edges();
+
+    keypad.set(keys(&[5]));
+    keypad.set(keys(&[]));
+    assert_eq!(wait(&mut keypad, &mut input_key), Some(5));
+  }
+
+  #[test]
+  fn key_held_from_before_has_to_be_pressed_again() {
+    let mut keypad = Keypad::new();
+    let mut input_key = None;
+    keypad.set(keys(&[3]));
+    keypad.clear_edges();
+
+    assert_eq!(wait(&mut keypad, &mut input_key), None);
+    keypad.set(keys(&[]));
+    assert_eq!(wait(&mut keypad, &mut input_key), None);
+    keypad.set(keys(&[3]));
+    assert_eq!(wait(&mut keypad, &mut input_key), None);
+    keypad.set(keys(&[]));
+    assert_eq!(wait(&mut keypad, &mut input_key), Some(3));
+  }
+
+  #[test]
+  fn held_key_released_and_pressed_between_steps() {
+    let mut keypad = Keypad::new();
+    let mut input_key = None;
+    keypad.set(keys(&[3]));
+    keypad.clear_edges();
+
+    keypad.set(keys(&[]));
+    keypad.set(keys(&[3]));
+    assert_eq!(wait(&mut keypad, &mut input_key), None);
+    keypad.set(keys(&[]));
+    assert_eq!(wait(&mut keypad, &mut input_key), Some(3));
+  }
+
+  #[test]
+  fn two_keys_held_at_once() {
+    let mut keypad = Keypad::new();
+    let mut input_key = None;
+    keypad.clear_edges();
+
+    keypad.set(keys(&[2, 9]));
+    assert_eq!(wait(&mut keypad, &mut input_key), None);
+    keypad.set(keys(&[2]));
+    assert_eq!(wait(&mut keypad, &mut input_key), None);
+    keypad.set(keys(&[]));
+    assert_eq!(wait(&mut keypad, &mut input_key), Some(2));
+  }
+
+  #[test]
+  fn bits_round_trip() {
+    let mut keypad = Keypad::new();
+    keypad.set(keys(&[0, 7]));
+    keypad.set(keys(&[7, 15]));
+
+    let bits = keypad.to_bits();
+    assert_eq!(bits, [1 << 7 | 1 << 15, 1 << 0 | 1 << 7 | 1 << 15, 1 << 0]);
+    assert_eq!(Keypad::from_bits(bits).to_bits(), bits);
+  }
+}
@@ -0,0 +1,41 @@
+pub mod coverage;
+pub mod cpu;
+pub mod error;
+pub mod font;
+pub mod instruction;
+pub mod keypad;
+pub mod memory;
+pub mod model;
+pub mod observer;
+pub mod profiler;
+pub mod quirks;
+pub mod random;
+mod recompiler;
+pub mod rewind;
+pub mod rom;
+pub mod screen;
+pub mod state;
+pub mod timing;
+pub mod tracer;
+
+#[cfg(feature = "sdl")]
+pub mod hardware;
+
+pub use crate::coverage::Coverage;
+pub use crate::cpu::{Chip8, Engine, InstructionSet, TickResult, CHIP8_HEIGHT, CHIP8_WIDTH, HIRES_CHIP8_HEIGHT, SCHIP_HEIGHT, SCHIP_WIDTH};
+pub use crate::error::Chip8Error;
+pub use crate::font::{Font, FontSet};
+pub use crate::instruction::{Instruction, UnknownOpcode};
+pub use crate::keypad::Keypad;
+pub use crate::memory::{Access, Memory, WatchHit, WatchpointId, MAX_WATCH_HITS};
+pub use crate::model::MachineModel;
+pub use crate::observer::{DrawEvent, FrameEvent, InstructionEvent, Observer, ObserverId, Registers, Timer};
+pub use crate::profiler::Profiler;
+pub use crate::quirks::Quirks;
+pub use crate::random::{RandomSource, XorShiftRandom};
+pub use crate::rewind::Rewind;
+pub use crate::rom::RomLoader;
+pub use crate::screen::{Screen, ALL_PLANES, PLANE_COUNT};
+pub use crate::state::StateError;
+pub use crate::timing::Timing;
+pub use crate::tracer::Tracer;
@@ -1,19 +1,245 @@
-mod cpu;
-mod hardware;
+use std::cell::RefCell;
+use std::env;
+use std::ops::Range;
+use std::fs;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chip8::hardware;
+use chip8::hardware::input::Hotkey;
+use chip8::{Chip8, Font, MachineModel, Profiler, Quirks, Rewind, RomLoader, Timing, Tracer};
+use chip8::rewind::DEFAULT_REWIND_FRAMES;
+
+const IDLE_SLEEP: Duration = Duration::from_millis(1);
+const ROM: &str = "roms/programs/Chip8 Picture.ch8";
+
+struct Options {
+  rom: String,
+  model: Option<MachineModel>,
+  font: Option<String>,
+  profile: bool,
+  coverage: bool,
+  trace: Option<String>,
+  trace_addresses: Option<Range<usize>>,
+  trace_cycles: Option<Range<u64>>,
+}
+
+fn usage() -> String {
+  let models: Vec<&str> = MachineModel::ALL.iter().map(|model| model.name()).collect();
+  format!("usage: chip8-rust [--model {}] [--font FILE] [--profile] [--coverage] [--trace FILE [--trace-addresses START-END] [--trace-cycles START-END]] [ROM]", models.join("|"))
+}
+
+fn parse_options() -> Result<Options, String> {
+  let mut options = Options { rom: ROM.to_string(), model: None, font: None, profile: false, coverage: false,
+    trace: None, trace_addresses: None, trace_cycles: None };
+
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--model" => {
+        let name = args.next().ok_or_else(usage)?;
+        options.model = Some(MachineModel::from_name(&name).ok_or_else(|| format!("Unknown model {}\n{}", name, usage()))?);
+      }
+      "--font" => options.font = Some(args.next().ok_or_else(usage)?),
+      "--profile" => options.profile = true,
+      "--coverage" => options.coverage = true,
+      "--trace" => options.trace = Some(args.next().ok_or_else(usage)?),
+      "--trace-addresses" => {
+        let range = parse_range(&args.next().ok_or_else(usage)?, 16)?;
+        options.trace_addresses = Some(range.start as usize..range.end as usize);
+      }
+      "--trace-cycles" => options.trace_cycles = Some(parse_range(&args.next().ok_or_else(usage)?, 10)?),
+      "--help" | "-h" => return Err(usage()),
+      _ if arg.starts_with('-') => return Err(format!("Unknown option {}\n{}", arg, usage())),
+      _ => options.rom = arg,
+    }
+  }
+
+  Ok(options)
+}
+
+// inclusive START-END, addresses are hex and cycles decimal
+fn parse_range(text: &str, radix: u32) -> Result<Range<u64>, String> {
+  let invalid = || format!("Invalid range {}\n{}", text, usage());
+  let mut bounds = text.splitn(2, '-');
+  let start = u64::from_str_radix(bounds.next().ok_or_else(invalid)?, radix).map_err(|_| invalid())?;
+  let end = u64::from_str_radix(bounds.next().ok_or_else(invalid)?, radix).map_err(|_| invalid())?;
+  Ok(start..end + 1)
+}
+
+fn create_tracer(options: &Options) -> Result<Option<Rc<RefCell<Tracer>>>, String> {
+  let path = match &options.trace {
+    Some(path) => path,
+    None => return Ok(None),
+  };
+
+  let mut tracer = Tracer::create(path).map_err(|error| format!("Failed to create trace {}: {}", path, error))?;
+  tracer.set_address_range(options.trace_addresses.clone());
+  tracer.set_cycle_window(options.trace_cycles.clone());
+  Ok(Some(Rc::new(RefCell::new(tracer))))
+}
+
+fn create_chip8(options: &Options) -> Result<Chip8, String> {
+  let mut chip8 = match options.model {
+    Some(model) => Chip8::with_model(model).map_err(|error| error.to_string())?,
+    None => Chip8::new(Quirks::default()),
+  };
+
+  if let Some(path) = &options.font {
+    let font = Font::load(path).map_err(|error| format!("Failed to load font: {}", error))?;
+    chip8.set_font(font).map_err(|error| error.to_string())?;
+  }
+
+  let loader = RomLoader::load(&options.rom).map_err(|error| format!("Failed to load {}: {}", options.rom, error))?;
+  chip8.load(loader.rom).map_err(|error| error.to_string())?;
+  Ok(chip8)
+}
+
+fn state_path(rom: &str, slot: u8) -> String {
+  format!("{}.state{}", rom, slot)
+}
+
+fn write_report(path: &str, report: &[u8]) {
+  match fs::write(path, report) {
+    Ok(()) => println!("Wrote {}", path),
+    Err(error) => eprintln!("Failed to write {}: {}", path, error),
+  }
+}
+
+fn write_profile(rom: &str, profiler: &Profiler) {
+  write_report(&format!("{}.profile.txt", rom), profiler.report_text().as_bytes());
+  write_report(&format!("{}.profile.json", rom), profiler.report_json().as_bytes());
+}
+
+fn write_coverage(rom: &str, chip8: &Chip8) {
+  if let Some(coverage) = chip8.memory().coverage() {
+    write_report(&format!("{}.coverage.txt", rom), coverage.to_text(chip8.rom_range()).as_bytes());
+    write_report(&format!("{}.coverage.ppm", rom), &coverage.to_overlay(chip8.rom_range()));
+  }
+}
 
 fn main() {
+  let options = match parse_options() {
+    Ok(options) => options,
+    Err(message) => {
+      eprintln!("{}", message);
+      return;
+    }
+  };
+
+  let mut chip8 = match create_chip8(&options) {
+    Ok(chip8) => chip8,
+    Err(message) => {
+      eprintln!("{}", message);
+      return;
+    }
+  };
+
+  if options.coverage {
+    chip8.memory_mut().enable_coverage();
+  }
+
+  let tracer = match create_tracer(&options) {
+    Ok(tracer) => tracer,
+    Err(message) => {
+      eprintln!("{}", message);
+      return;
+    }
+  };
+  if let Some(tracer) = &tracer {
+    chip8.add_observer(Box::new(tracer.clone()));
+  }
+
+  let profiler = if options.profile {
+    let profiler = Rc::new(RefCell::new(Profiler::new(chip8.timer_frequency())));
+    chip8.add_observer(Box::new(profiler.clone()));
+    Some(profiler)
+  } else {
+    None
+  };
+
   let sdl = sdl2::init().unwrap();
 
-  let rom_loader = hardware::rom::RomLoader::load("roms/programs/Chip8 Picture.ch8");
   let mut display = hardware::display::Display::new(&sdl);
   let mut input = hardware::input::Input::new(&sdl);
   let mut sound = hardware::sound::Sound::new(&sdl);
 
-  let mut chip8 = cpu::Chip8::new();
-  chip8.load(rom_loader.rom);
+  let mut rewind = Rewind::new(DEFAULT_REWIND_FRAMES);
+  rewind.record(chip8.save_state());
+  let mut rewind_time = Duration::from_secs(0);
+
+  let mut last_update = Instant::now();
+  let mut slot = 1;
 
   while let Ok(input_state) = input.process_input() {
-    let tick_result = chip8.tick(input_state);
+    for hotkey in input_state.hotkeys {
+      let instructions_per_frame = chip8.instructions_per_frame();
+      match hotkey {
+        Hotkey::SpeedUp | Hotkey::SpeedDown if chip8.timing() == Timing::CosmacVip => {
+          println!("Speed is set by the COSMAC VIP timing model");
+        }
+        Hotkey::SpeedUp => {
+          chip8.set_instructions_per_frame(instructions_per_frame + 1);
+          println!("Speed: {} instructions per frame", chip8.instructions_per_frame());
+        }
+        Hotkey::SpeedDown => {
+          chip8.set_instructions_per_frame(instructions_per_frame - 1);
+          println!("Speed: {} instructions per frame", chip8.instructions_per_frame());
+        }
+        Hotkey::SelectSlot(selected) => slot = selected,
+        Hotkey::SaveState => match fs::write(state_path(&options.rom, slot), chip8.save_state()) {
+          Ok(()) => println!("Saved state to slot {}", slot),
+          Err(error) => eprintln!("Failed to save state: {}", error),
+        },
+        Hotkey::LoadState => match fs::read(state_path(&options.rom, slot)) {
+          Ok(data) => match chip8.load_state(&data) {
+            Ok(()) => println!("Loaded state from slot {}", slot),
+            Err(error) => eprintln!("Failed to load state: {}", error),
+          },
+          Err(error) => eprintln!("Failed to load state: {}", error),
+        },
+      }
+    }
+
+    let now = Instant::now();
+    let elapsed = now - last_update;
+    last_update = now;
+
+    if input_state.rewind {
+      rewind_time += elapsed;
+
+      let mut rewound = false;
+      while rewind_time >= chip8.frame_duration() {
+        rewind_time -= chip8.frame_duration();
+
+        if let Some(state) = rewind.rewind() {
+          if let Err(error) = chip8.load_state(state) {
+            eprintln!("Failed to rewind: {}", error);
+          }
+          rewound = true;
+        }
+      }
+
+      if rewound {
+        display.draw_screen(chip8.screen_buffer());
+      }
+      sound.stop();
+
+      thread::sleep(IDLE_SLEEP);
+      continue;
+    }
+    rewind_time = Duration::from_secs(0);
+
+    let frame_count = chip8.frame_count();
+    chip8.set_input(input_state.keys);
+    let tick_result = match chip8.update(elapsed) {
+      Ok(tick_result) => tick_result,
+      Err(error) => {
+        eprintln!("{}", error);
+        break;
+      }
+    };
 
     if tick_result.screen_changed {
       display.draw_screen(tick_result.screen_buffer);
@@ -24,5 +250,26 @@ fn main() {
     } else {
       sound.stop();
     }
+
+    if tick_result.exited {
+      break;
+    }
+
+    if chip8.frame_count() != frame_count {
+      rewind.record(chip8.save_state());
+    }
+
+    thread::sleep(IDLE_SLEEP);
+  }
+
+  if let Some(profiler) = profiler {
+    write_profile(&options.rom, &profiler.borrow());
+  }
+  write_coverage(&options.rom, &chip8);
+
+  if let Some(tracer) = tracer {
+    if let Err(error) = tracer.borrow_mut().finish() {
+      eprintln!("Failed to write trace: {}", error);
+    }
   }
-}
\ No newline at end of file
+}
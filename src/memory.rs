@@ -0,0 +1,345 @@
+use std::ops::Range;
+
+use crate::coverage::{Coverage, EXECUTED, READ, WRITTEN};
+use crate::error::Chip8Error;
+use crate::instruction::Instruction;
+
+pub const MAX_WATCH_HITS: usize = 4096; //hits kept until take_hits, later ones are only counted
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+  Read,
+  Write,
+  Execute,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct WatchpointId(usize);
+
+struct Watchpoint {
+  id: WatchpointId,
+  range: Range<usize>,
+  access: Access,
+}
+
+// a watched access, value is the byte read or written, for execute the first byte of the opcode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchHit {
+  pub id: WatchpointId,
+  pub address: usize,
+  pub access: Access,
+  pub value: u8,
+}
+
+// all interpreter memory traffic goes through here so it can be watched
+// it also keeps the decoded instruction for every address that was executed,
+// any change to the bytes of an instruction drops its cache entry
+pub struct Memory {
+  data: Vec<u8>,
+  decoded: Vec<Option<Instruction>>,
+  code_modified: bool, //a cached instruction was overwritten since the last check
+  watchpoints: Vec<Watchpoint>,
+  next_watchpoint: usize,
+  hits: Vec<WatchHit>,
+  dropped_hits: usize,
+  coverage: Option<Coverage>,
+}
+
+impl Memory {
+  pub fn new(size: usize) -> Memory {
+    Memory {
+      data: vec![0; size],
+      decoded: Vec::new(),
+      code_modified: false,
+      watchpoints: Vec::new(),
+      next_watchpoint: 0,
+      hits: Vec::new(),
+      dropped_hits: 0,
+      coverage: None,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.data.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn resize(&mut self, size: usize) {
+    self.data.resize(size, 0);
+    self.clear_decoded();
+
+    if let Some(coverage) = self.coverage.as_mut() {
+      coverage.resize(size);
+    }
+  }
+
+  pub fn as_slice(&self) -> &[u8] {
+    &self.data
+  }
+
+  // replaces the whole content, used by the loader and save states - not watched
+  pub fn replace(&mut self, data: Vec<u8>) {
+    self.data = data;
+    self.clear_decoded();
+  }
+
+  // writes without triggering watchpoints, used to load fonts and roms
+  pub fn copy_from(&mut self, address: usize, bytes: &[u8]) -> Result<(), Chip8Error> {
+    match self.data.get_mut(address..address + bytes.len()) {
+      Some(target) => {
+        target.copy_from_slice(bytes);
+        self.invalidate(address..address + bytes.len());
+        Ok(())
+      }
+      None => Err(Chip8Error::MemoryOutOfRange { address: address + bytes.len() })
+    }
+  }
+
+  // reads without triggering watchpoints, for debuggers and other tools
+  pub fn peek(&self, address: usize) -> Option<u8> {
+    self.data.get(address).copied()
+  }
+
+  pub fn peek_word(&self, address: usize) -> Option<u16> {
+    Some((self.peek(address)? as u16) << 8 | self.peek(address + 1)? as u16)
+  }
+
+  pub fn read(&mut self, address: usize) -> Result<u8, Chip8Error> {
+    let value = self.peek(address).ok_or(Chip8Error::MemoryOutOfRange { address })?;
+    self.watch(address, Access::Read, value);
+    self.cover(address, READ);
+    Ok(value)
+  }
+
+  pub fn write(&mut self, address: usize, value: u8) -> Result<(), Chip8Error> {
+    match self.data.get_mut(address) {
+      Some(byte) => *byte = value,
+      None => return Err(Chip8Error::MemoryOutOfRange { address })
+    }
+
+    self.invalidate(address..address + 1);
+    self.watch(address, Access::Write, value);
+    self.cover(address, WRITTEN);
+    Ok(())
+  }
+
+  // fetches the opcode at address
+  pub fn fetch(&mut self, address: usize) -> Result<u16, Chip8Error> {
+    let op = self.peek_word(address).ok_or(Chip8Error::ProgramCounterOutOfRange { address })?;
+    self.watch(address, Access::Execute, (op >> 8) as u8);
+    self.cover_execute(address, 2);
+    Ok(op)
+  }
+
+  // marks the words after a fetched opcode as executed when the instruction turned out to be longer
+  pub(crate) fn cover_operand(&mut self, address: usize, size: usize) {
+    if size > 2 {
+      self.cover_execute(address + 2, size - 2);
+    }
+  }
+
+  // the cached instruction at address, counts as a fetch for the watchpoints
+  pub fn fetch_decoded(&mut self, address: usize) -> Option<Instruction> {
+    let instruction = self.decoded.get(address).copied().flatten()?;
+    self.watch(address, Access::Execute, self.data[address]);
+    self.cover_execute(address, instruction.size());
+    Some(instruction)
+  }
+
+  pub fn set_decoded(&mut self, address: usize, instruction: Instruction) {
+    if self.decoded.len() != self.data.len() {
+      self.decoded.resize(self.data.len(), None);
+    }
+
+    if let Some(entry) = self.decoded.get_mut(address) {
+      *entry = Some(instruction);
+    }
+  }
+
+  pub fn clear_decoded(&mut self) {
+    self.decoded.clear();
+    self.code_modified = true;
+  }
+
+  // true if any cached instruction changed since the last call
+  pub fn take_code_modified(&mut self) -> bool {
+    std::mem::replace(&mut self.code_modified, false)
+  }
+
+  // records the execution of an already decoded instruction of size bytes
+  pub(crate) fn executed(&mut self, address: usize, size: usize) {
+    if !self.watchpoints.is_empty() {
+      self.watch(address, Access::Execute, self.data[address]);
+    }
+    self.cover_execute(address, size);
+  }
+
+  // starts recording how every address is accessed, see coverage
+  pub fn enable_coverage(&mut self) {
+    if self.coverage.is_none() {
+      self.coverage = Some(Coverage::new(self.data.len()));
+    }
+  }
+
+  pub fn disable_coverage(&mut self) {
+    self.coverage = None;
+  }
+
+  pub fn coverage(&self) -> Option<&Coverage> {
+    self.coverage.as_ref()
+  }
+
+  pub fn clear_coverage(&mut self) {
+    if let Some(coverage) = self.coverage.as_mut() {
+      coverage.clear();
+    }
+  }
+
+  pub fn add_watchpoint(&mut self, range: Range<usize>, access: Access) -> WatchpointId {
+    let id = WatchpointId(self.next_watchpoint);
+    self.next_watchpoint += 1;
+    self.watchpoints.push(Watchpoint { id, range, access });
+    id
+  }
+
+  pub fn remove_watchpoint(&mut self, id: WatchpointId) {
+    self.watchpoints.retain(|watchpoint| watchpoint.id != id);
+  }
+
+  pub fn has_hits(&self) -> bool {
+    !self.hits.is_empty()
+  }
+
+  // returns and forgets the watched accesses since the last call, at most MAX_WATCH_HITS of them
+  pub fn take_hits(&mut self) -> Vec<WatchHit> {
+    self.dropped_hits = 0;
+    std::mem::take(&mut self.hits)
+  }
+
+  // watched accesses since the last take_hits that didn't fit into the buffer
+  pub fn dropped_hits(&self) -> usize {
+    self.dropped_hits
+  }
+
+  // instructions are up to 4 bytes long, so a write also hits the ones starting before it
+  fn invalidate(&mut self, range: Range<usize>) {
+    let start = range.start.saturating_sub(3);
+    let end = range.end.min(self.decoded.len());
+    for entry in self.decoded.iter_mut().take(end).skip(start) {
+      if entry.take().is_some() {
+        self.code_modified = true;
+      }
+    }
+  }
+
+  fn cover(&mut self, address: usize, flag: u8) {
+    if let Some(coverage) = self.coverage.as_mut() {
+      coverage.mark(address, flag);
+    }
+  }
+
+  fn cover_execute(&mut self, address: usize, size: usize) {
+    if let Some(coverage) = self.coverage.as_mut() {
+      for offset in 0..size {
+        coverage.mark(address + offset, EXECUTED);
+      }
+    }
+  }
+
+  fn watch(&mut self, address: usize, access: Access, value: u8) {
+    for watchpoint in self.watchpoints.iter() {
+      if watchpoint.access == access && watchpoint.range.contains(&address) {
+        if self.hits.len() < MAX_WATCH_HITS {
+          self.hits.push(WatchHit { id: watchpoint.id, address, access, value });
+        } else {
+          self.dropped_hits += 1;
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn addresses(hits: &[WatchHit]) -> Vec<(usize, Access, u8)> {
+    hits.iter().map(|hit| (hit.address, hit.access, hit.value)).collect()
+  }
+
+  #[test]
+  fn watchpoints_fire_on_their_range_and_access() {
+    let mut memory = Memory::new(0x1000);
+    memory.copy_from(0x300, &[0x12, 0x34, 0x56, 0x78]).unwrap();
+    memory.add_watchpoint(0x301..0x303, Access::Read);
+    memory.add_watchpoint(0x302..0x304, Access::Write);
+    memory.add_watchpoint(0x300..0x301, Access::Execute);
+    assert!(!memory.has_hits());
+
+    for address in 0x2FF..0x305 {
+      memory.read(address).unwrap();
+      memory.write(address, 0xAA).unwrap();
+    }
+    memory.fetch(0x300).unwrap();
+    memory.fetch(0x302).unwrap();
+
+    assert_eq!(addresses(&memory.take_hits()), vec![
+      (0x301, Access::Read, 0x34),
+      (0x302, Access::Read, 0x56),
+      (0x302, Access::Write, 0xAA),
+      (0x303, Access::Write, 0xAA),
+      (0x300, Access::Execute, 0xAA),
+    ]);
+    assert!(memory.take_hits().is_empty());
+  }
+
+  #[test]
+  fn cached_instructions_count_as_executed() {
+    let mut memory = Memory::new(0x1000);
+    memory.copy_from(0x200, &[0x00, 0xE0]).unwrap();
+    memory.set_decoded(0x200, Instruction::ClearScreen);
+    memory.add_watchpoint(0x200..0x202, Access::Execute);
+
+    assert_eq!(memory.fetch_decoded(0x200), Some(Instruction::ClearScreen));
+    memory.executed(0x200, 2);
+
+    assert_eq!(addresses(&memory.take_hits()), vec![(0x200, Access::Execute, 0x00); 2]);
+  }
+
+  #[test]
+  fn removed_watchpoints_stop_firing() {
+    let mut memory = Memory::new(0x1000);
+    let read = memory.add_watchpoint(0x300..0x310, Access::Read);
+    let write = memory.add_watchpoint(0x300..0x310, Access::Write);
+    memory.remove_watchpoint(read);
+
+    memory.read(0x308).unwrap();
+    memory.write(0x308, 1).unwrap();
+    assert_eq!(addresses(&memory.take_hits()), vec![(0x308, Access::Write, 1)]);
+
+    memory.remove_watchpoint(write);
+    memory.read(0x308).unwrap();
+    memory.write(0x308, 2).unwrap();
+    assert!(!memory.has_hits());
+  }
+
+  #[test]
+  fn hits_are_capped_until_taken() {
+    let mut memory = Memory::new(0x1000);
+    memory.add_watchpoint(0x200..0x202, Access::Execute);
+
+    for _ in 0..MAX_WATCH_HITS + 10 {
+      memory.fetch(0x200).unwrap();
+    }
+
+    assert_eq!(memory.dropped_hits(), 10);
+    assert_eq!(memory.take_hits().len(), MAX_WATCH_HITS);
+    assert_eq!(memory.dropped_hits(), 0);
+
+    memory.fetch(0x200).unwrap();
+    assert_eq!(memory.take_hits().len(), 1);
+  }
+}
@@ -0,0 +1,113 @@
+use crate::cpu::{InstructionSet, DEFAULT_PROGRAM_START, DEFAULT_STACK_DEPTH};
+use crate::font::FontSet;
+use crate::quirks::Quirks;
+use crate::timing::Timing;
+
+const ETI_660_PROGRAM_START: usize = 0x0600;
+const VIP_STACK_DEPTH: usize = 12;
+
+// the machines chip-8 programs were written for, each one bundles everything that differs between them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MachineModel {
+  CosmacVip,
+  Eti660,
+  Chip48,
+  SuperChip10,
+  SuperChip11,
+  XoChip,
+}
+
+impl MachineModel {
+  pub const ALL: [MachineModel; 6] = [
+    MachineModel::CosmacVip,
+    MachineModel::Eti660,
+    MachineModel::Chip48,
+    MachineModel::SuperChip10,
+    MachineModel::SuperChip11,
+    MachineModel::XoChip,
+  ];
+
+  // short name for the command line
+  pub fn name(&self) -> &'static str {
+    match self {
+      MachineModel::CosmacVip => "vip",
+      MachineModel::Eti660 => "eti660",
+      MachineModel::Chip48 => "chip48",
+      MachineModel::SuperChip10 => "schip10",
+      MachineModel::SuperChip11 => "schip11",
+      MachineModel::XoChip => "xochip",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<MachineModel> {
+    MachineModel::ALL.iter().copied().find(|model| model.name() == name)
+  }
+
+  pub fn instruction_set(&self) -> InstructionSet {
+    match self {
+      MachineModel::CosmacVip | MachineModel::Eti660 | MachineModel::Chip48 => InstructionSet::Chip8,
+      MachineModel::SuperChip10 => InstructionSet::SuperChip10,
+      MachineModel::SuperChip11 => InstructionSet::SuperChip,
+      MachineModel::XoChip => InstructionSet::XoChip,
+    }
+  }
+
+  pub fn program_start(&self) -> usize {
+    match self {
+      MachineModel::Eti660 => ETI_660_PROGRAM_START,
+      _ => DEFAULT_PROGRAM_START,
+    }
+  }
+
+  pub fn memory_size(&self) -> usize {
+    self.instruction_set().memory_size()
+  }
+
+  pub fn resolution(&self) -> (usize, usize) {
+    self.instruction_set().screen_size()
+  }
+
+  pub fn stack_depth(&self) -> usize {
+    match self {
+      MachineModel::CosmacVip => VIP_STACK_DEPTH,
+      _ => DEFAULT_STACK_DEPTH,
+    }
+  }
+
+  // only the vip interpreter kept its stack in memory programs could see
+  pub fn stack_in_memory(&self) -> bool {
+    *self == MachineModel::CosmacVip
+  }
+
+  pub fn font_set(&self) -> FontSet {
+    match self {
+      MachineModel::CosmacVip => FontSet::CosmacVip,
+      MachineModel::Eti660 => FontSet::Eti660,
+      _ => FontSet::Chip48,
+    }
+  }
+
+  pub fn quirks(&self) -> Quirks {
+    match self {
+      MachineModel::CosmacVip | MachineModel::Eti660 => Quirks::cosmac_vip(),
+      MachineModel::Chip48 | MachineModel::SuperChip10 | MachineModel::SuperChip11 => Quirks::superchip(),
+      MachineModel::XoChip => Quirks::xochip(),
+    }
+  }
+
+  pub fn timing(&self) -> Timing {
+    match self {
+      MachineModel::CosmacVip => Timing::CosmacVip,
+      _ => Timing::Simple,
+    }
+  }
+
+  // used with the simple timing model
+  pub fn instructions_per_frame(&self) -> usize {
+    match self {
+      MachineModel::CosmacVip | MachineModel::Eti660 => 15,
+      MachineModel::Chip48 | MachineModel::SuperChip10 | MachineModel::SuperChip11 => 30,
+      MachineModel::XoChip => 1000,
+    }
+  }
+}
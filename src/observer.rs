@@ -0,0 +1,107 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::instruction::Instruction;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObserverId(pub(crate) usize);
+
+// the programmer visible machine state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Registers {
+  pub v: [u8; 16],
+  pub i: u16,
+  pub delay_timer: u8,
+  pub sound_timer: u8,
+  pub program_counter: usize,
+  pub stack_pointer: usize,
+}
+
+impl Registers {
+  // indices of the v registers that differ
+  pub fn changed_v(&self, other: &Registers) -> impl Iterator<Item = usize> + '_ {
+    let other = other.v;
+    (0..self.v.len()).filter(move |x| self.v[*x] != other[*x])
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstructionEvent {
+  pub program_counter: usize,
+  pub opcode: u16,
+  pub instruction: Instruction,
+}
+
+// the screen area a sprite was drawn to, x and y are already wrapped to the screen
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DrawEvent {
+  pub x: usize,
+  pub y: usize,
+  pub width: usize,
+  pub height: usize,
+  pub planes: u8,
+  pub collision: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FrameEvent {
+  pub frame: u64,
+  pub waiting_for_key: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timer {
+  Delay,
+  Sound,
+}
+
+// callbacks from the core, every method does nothing by default
+pub trait Observer {
+  fn before_instruction(&mut self, _event: &InstructionEvent, _registers: &Registers) {}
+
+  // only called when the instruction succeeded, before holds the registers at the time before_instruction was called
+  fn after_instruction(&mut self, _event: &InstructionEvent, _before: &Registers, _after: &Registers) {}
+
+  fn draw(&mut self, _event: &DrawEvent) {}
+
+  fn clear_screen(&mut self, _planes: u8) {}
+
+  // the program wrote a timer, the countdown doesn't count as a write
+  fn timer_write(&mut self, _timer: Timer, _value: u8) {}
+
+  fn sound(&mut self, _playing: bool) {}
+
+  // a frame ended and the timers were decremented
+  fn frame(&mut self, _event: &FrameEvent) {}
+}
+
+// lets the caller keep a handle to read results from an observer registered with the core
+impl<T: Observer> Observer for Rc<RefCell<T>> {
+  fn before_instruction(&mut self, event: &InstructionEvent, registers: &Registers) {
+    self.borrow_mut().before_instruction(event, registers);
+  }
+
+  fn after_instruction(&mut self, event: &InstructionEvent, before: &Registers, after: &Registers) {
+    self.borrow_mut().after_instruction(event, before, after);
+  }
+
+  fn draw(&mut self, event: &DrawEvent) {
+    self.borrow_mut().draw(event);
+  }
+
+  fn clear_screen(&mut self, planes: u8) {
+    self.borrow_mut().clear_screen(planes);
+  }
+
+  fn timer_write(&mut self, timer: Timer, value: u8) {
+    self.borrow_mut().timer_write(timer, value);
+  }
+
+  fn sound(&mut self, playing: bool) {
+    self.borrow_mut().sound(playing);
+  }
+
+  fn frame(&mut self, event: &FrameEvent) {
+    self.borrow_mut().frame(event);
+  }
+}
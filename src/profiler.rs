@@ -0,0 +1,210 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::instruction::Instruction;
+use crate::observer::{FrameEvent, InstructionEvent, Observer, Registers};
+
+const HOT_SPOTS: usize = 20; //addresses listed in the text report
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Subroutine {
+  pub calls: u64,
+  pub returns: u64,
+}
+
+// counts where a rom spends its time, register it with Chip8::add_observer
+pub struct Profiler {
+  timer_frequency: u32,
+  instructions: u64,
+  addresses: HashMap<usize, (u64, Instruction)>,
+  patterns: BTreeMap<&'static str, u64>,
+  frames: u64,
+  frame_instructions: u64,
+  min_frame_instructions: u64,
+  max_frame_instructions: u64,
+  blocked_frames: u64,
+  blocked_since: Option<Instant>,
+  blocked_time: Duration,
+  subroutines: BTreeMap<usize, Subroutine>,
+  call_stack: Vec<usize>,
+}
+
+impl Profiler {
+  pub fn new(timer_frequency: u32) -> Profiler {
+    Profiler {
+      timer_frequency: timer_frequency.max(1),
+      instructions: 0,
+      addresses: HashMap::new(),
+      patterns: BTreeMap::new(),
+      frames: 0,
+      frame_instructions: 0,
+      min_frame_instructions: u64::MAX,
+      max_frame_instructions: 0,
+      blocked_frames: 0,
+      blocked_since: None,
+      blocked_time: Duration::from_secs(0),
+      subroutines: BTreeMap::new(),
+      call_stack: Vec::new(),
+    }
+  }
+
+  pub fn instructions(&self) -> u64 {
+    self.instructions
+  }
+
+  pub fn frames(&self) -> u64 {
+    self.frames
+  }
+
+  pub fn executions(&self, address: usize) -> u64 {
+    self.addresses.get(&address).map_or(0, |(count, _)| *count)
+  }
+
+  pub fn pattern_count(&self, pattern: &str) -> u64 {
+    self.patterns.get(pattern).copied().unwrap_or(0)
+  }
+
+  pub fn subroutines(&self) -> &BTreeMap<usize, Subroutine> {
+    &self.subroutines
+  }
+
+  // frames the program spent halted in fx0a
+  pub fn blocked_frames(&self) -> u64 {
+    self.blocked_frames
+  }
+
+  // wall clock time the program spent halted in fx0a
+  pub fn blocked_time(&self) -> Duration {
+    self.blocked_time + self.blocked_since.map_or(Duration::from_secs(0), |since| since.elapsed())
+  }
+
+  fn average_frame_instructions(&self) -> f64 {
+    if self.frames == 0 {
+      0.0
+    } else {
+      self.instructions as f64 / self.frames as f64
+    }
+  }
+
+  fn min_frame_instructions(&self) -> u64 {
+    if self.frames == 0 { 0 } else { self.min_frame_instructions }
+  }
+
+  // addresses sorted by execution count, most executed first
+  fn hot_spots(&self) -> Vec<(usize, u64, Instruction)> {
+    let mut hot_spots: Vec<(usize, u64, Instruction)> = self.addresses.iter()
+      .map(|(address, (count, instruction))| (*address, *count, *instruction))
+      .collect();
+    hot_spots.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    hot_spots
+  }
+
+  fn percent(&self, count: u64) -> f64 {
+    if self.instructions == 0 {
+      0.0
+    } else {
+      count as f64 * 100.0 / self.instructions as f64
+    }
+  }
+
+  pub fn report_text(&self) -> String {
+    let mut report = String::new();
+
+    writeln!(report, "Instructions: {} in {} frames", self.instructions, self.frames).unwrap();
+    writeln!(report, "Per frame: {:.1} average, {} min, {} max",
+      self.average_frame_instructions(), self.min_frame_instructions(), self.max_frame_instructions).unwrap();
+    writeln!(report, "Blocked in FX0A: {} frames ({:.2} s emulated, {:.2} s real)", self.blocked_frames,
+      self.blocked_frames as f64 / self.timer_frequency as f64, self.blocked_time().as_secs_f64()).unwrap();
+
+    writeln!(report, "\nOpcodes:").unwrap();
+    let mut patterns: Vec<(&&str, &u64)> = self.patterns.iter().collect();
+    patterns.sort_by(|a, b| b.1.cmp(a.1));
+    for (pattern, count) in patterns {
+      writeln!(report, "  {}  {:>12}  {:>5.1}%", pattern, count, self.percent(*count)).unwrap();
+    }
+
+    writeln!(report, "\nHot spots:").unwrap();
+    for (address, count, instruction) in self.hot_spots().into_iter().take(HOT_SPOTS) {
+      writeln!(report, "  {:04X}  {:>12}  {:>5.1}%  {}", address, count, self.percent(count), instruction).unwrap();
+    }
+
+    writeln!(report, "\nSubroutines:").unwrap();
+    for (address, subroutine) in self.subroutines.iter() {
+      writeln!(report, "  {:04X}  {:>8} calls  {:>8} returns", address, subroutine.calls, subroutine.returns).unwrap();
+    }
+
+    report
+  }
+
+  pub fn report_json(&self) -> String {
+    let mut report = String::new();
+
+    writeln!(report, "{{").unwrap();
+    writeln!(report, "  \"instructions\": {},", self.instructions).unwrap();
+    writeln!(report, "  \"frames\": {},", self.frames).unwrap();
+    writeln!(report, "  \"instructions_per_frame\": {{ \"average\": {:.3}, \"min\": {}, \"max\": {} }},",
+      self.average_frame_instructions(), self.min_frame_instructions(), self.max_frame_instructions).unwrap();
+    writeln!(report, "  \"blocked_in_fx0a\": {{ \"frames\": {}, \"seconds\": {:.3} }},",
+      self.blocked_frames, self.blocked_time().as_secs_f64()).unwrap();
+
+    let patterns: Vec<String> = self.patterns.iter()
+      .map(|(pattern, count)| format!("\"{}\": {}", pattern, count))
+      .collect();
+    writeln!(report, "  \"opcodes\": {{ {} }},", patterns.join(", ")).unwrap();
+
+    let addresses: Vec<String> = self.hot_spots().into_iter()
+      .map(|(address, count, instruction)| format!("    {{ \"address\": {}, \"count\": {}, \"instruction\": \"{}\" }}", address, count, instruction))
+      .collect();
+    writeln!(report, "  \"addresses\": [\n{}\n  ],", addresses.join(",\n")).unwrap();
+
+    let subroutines: Vec<String> = self.subroutines.iter()
+      .map(|(address, subroutine)| format!("    {{ \"address\": {}, \"calls\": {}, \"returns\": {} }}", address, subroutine.calls, subroutine.returns))
+      .collect();
+    writeln!(report, "  \"subroutines\": [\n{}\n  ]", subroutines.join(",\n")).unwrap();
+    writeln!(report, "}}").unwrap();
+
+    report
+  }
+}
+
+impl Observer for Profiler {
+  fn before_instruction(&mut self, event: &InstructionEvent, _registers: &Registers) {
+    if let Some(since) = self.blocked_since.take() {
+      self.blocked_time += since.elapsed();
+    }
+
+    self.instructions += 1;
+    self.frame_instructions += 1;
+    self.addresses.entry(event.program_counter).or_insert((0, event.instruction)).0 += 1;
+    *self.patterns.entry(event.instruction.pattern()).or_insert(0) += 1;
+  }
+
+  // calls and returns are only counted once they succeeded, a 2nnn that overflows the stack isn't a call
+  fn after_instruction(&mut self, event: &InstructionEvent, _before: &Registers, _after: &Registers) {
+    match event.instruction {
+      Instruction::Call(address) => {
+        self.subroutines.entry(address as usize).or_default().calls += 1;
+        self.call_stack.push(address as usize);
+      }
+      Instruction::Return => {
+        if let Some(address) = self.call_stack.pop() {
+          self.subroutines.entry(address).or_default().returns += 1;
+        }
+      }
+      Instruction::WaitForKey(_) => self.blocked_since = Some(Instant::now()),
+      _ => {}
+    }
+  }
+
+  fn frame(&mut self, event: &FrameEvent) {
+    self.frames += 1;
+    self.min_frame_instructions = self.min_frame_instructions.min(self.frame_instructions);
+    self.max_frame_instructions = self.max_frame_instructions.max(self.frame_instructions);
+    self.frame_instructions = 0;
+
+    if event.waiting_for_key {
+      self.blocked_frames += 1;
+    }
+  }
+}
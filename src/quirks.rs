@@ -0,0 +1,64 @@
+// behaviour of the opcodes that were implemented differently by the various interpreters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+  pub shift_uses_vy: bool,           // 8xy6/8xye shift vy into vx instead of shifting vx in place
+  pub load_store_increments_i: bool, // fx55/fx65 leave i pointing behind the last register
+  pub jump_uses_vx: bool,            // bnnn jumps to xnn + vx instead of nnn + v0
+  pub vf_reset: bool,                // 8xy1/8xy2/8xy3 reset vf to 0
+  pub clip_sprites: bool,            // dxyn clips sprites at the screen edges instead of wrapping them
+  pub display_wait: bool,            // dxyn waits for the next timer tick before continuing
+  pub i_overflow_flag: bool,         // fx1e sets vf when i overflows
+}
+
+impl Default for Quirks {
+  fn default() -> Self {
+    Quirks {
+      shift_uses_vy: false,
+      load_store_increments_i: false,
+      jump_uses_vx: false,
+      vf_reset: false,
+      clip_sprites: false,
+      display_wait: false,
+      i_overflow_flag: true,
+    }
+  }
+}
+
+impl Quirks {
+  pub fn cosmac_vip() -> Quirks {
+    Quirks {
+      shift_uses_vy: true,
+      load_store_increments_i: true,
+      jump_uses_vx: false,
+      vf_reset: true,
+      clip_sprites: true,
+      display_wait: true,
+      i_overflow_flag: false,
+    }
+  }
+
+  // also used by chip-48, which superchip is based on
+  pub fn superchip() -> Quirks {
+    Quirks {
+      shift_uses_vy: false,
+      load_store_increments_i: false,
+      jump_uses_vx: true,
+      vf_reset: false,
+      clip_sprites: true,
+      display_wait: false,
+      i_overflow_flag: false,
+    }
+  }
+
+  pub fn xochip() -> Quirks {
+    Quirks {
+      shift_uses_vy: true,
+      load_store_increments_i: true,
+      jump_uses_vx: false,
+      vf_reset: false,
+      clip_sprites: false,
+      display_wait: false,
+      i_overflow_flag: false,
+    }
+  }
+}
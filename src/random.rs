@@ -0,0 +1,54 @@
+// source of the random bytes used by cxkk
+// the state has to be readable and restorable so that snapshots of the machine replay identically
+pub trait RandomSource {
+  fn next_byte(&mut self) -> u8;
+  fn seed(&self) -> u64;
+  fn reseed(&mut self, seed: u64);
+  fn state(&self) -> u64;
+  fn restore_state(&mut self, state: u64);
+}
+
+// xorshift64* - small, fast and fully described by a single u64
+pub struct XorShiftRandom {
+  seed: u64,
+  state: u64,
+}
+
+impl XorShiftRandom {
+  pub fn new(seed: u64) -> XorShiftRandom {
+    let mut random = XorShiftRandom { seed, state: 0 };
+    random.reseed(seed);
+    random
+  }
+
+  pub fn from_entropy() -> XorShiftRandom {
+    XorShiftRandom::new(rand::random::<u64>())
+  }
+}
+
+impl RandomSource for XorShiftRandom {
+  fn next_byte(&mut self) -> u8 {
+    self.state ^= self.state >> 12;
+    self.state ^= self.state << 25;
+    self.state ^= self.state >> 27;
+    (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+  }
+
+  fn seed(&self) -> u64 {
+    self.seed
+  }
+
+  fn reseed(&mut self, seed: u64) {
+    self.seed = seed;
+    //xorshift gets stuck on a zero state
+    self.state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+  }
+
+  fn state(&self) -> u64 {
+    self.state
+  }
+
+  fn restore_state(&mut self, state: u64) {
+    self.state = state;
+  }
+}
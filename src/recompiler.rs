@@ -0,0 +1,77 @@
+use std::rc::Rc;
+
+use crate::cpu::{Chip8, ProgramCounterAction};
+use crate::error::Chip8Error;
+use crate::instruction::Instruction;
+
+const MAX_BLOCK_LENGTH: usize = 64;
+
+// an instruction with its operands already bound
+pub(crate) type Operation = Box<dyn Fn(&mut Chip8) -> Result<ProgramCounterAction, Chip8Error>>;
+
+pub(crate) struct CompiledInstruction {
+  pub address: usize,
+  pub size: usize,
+  pub operation: Operation,
+}
+
+// straight line code starting at an address, the last instruction is the only one that may branch
+pub(crate) struct Block {
+  pub instructions: Vec<CompiledInstruction>,
+}
+
+impl Block {
+  pub fn new() -> Block {
+    Block { instructions: Vec::new() }
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.instructions.len() >= MAX_BLOCK_LENGTH
+  }
+}
+
+// translated blocks by start address, thrown away as a whole whenever the program writes over code
+pub(crate) struct BlockCache {
+  blocks: Vec<Option<Rc<Block>>>,
+}
+
+impl BlockCache {
+  pub fn new() -> BlockCache {
+    BlockCache { blocks: Vec::new() }
+  }
+
+  pub fn get(&self, address: usize) -> Option<Rc<Block>> {
+    self.blocks.get(address)?.clone()
+  }
+
+  pub fn insert(&mut self, address: usize, block: Block) -> Rc<Block> {
+    if self.blocks.len() <= address {
+      self.blocks.resize(address + 1, None);
+    }
+
+    let block = Rc::new(block);
+    self.blocks[address] = Some(block.clone());
+    block
+  }
+
+  pub fn clear(&mut self) {
+    self.blocks.clear();
+  }
+}
+
+// everything that does not simply continue with the next instruction ends a block
+pub(crate) fn ends_block(instruction: &Instruction) -> bool {
+  matches!(instruction,
+    Instruction::Return
+    | Instruction::Jump(_)
+    | Instruction::Call(_)
+    | Instruction::SkipEqualByte(_, _)
+    | Instruction::SkipNotEqualByte(_, _)
+    | Instruction::SkipEqual(_, _)
+    | Instruction::SkipNotEqual(_, _)
+    | Instruction::JumpOffset(_)
+    | Instruction::SkipKeyPressed(_)
+    | Instruction::SkipKeyNotPressed(_)
+    | Instruction::WaitForKey(_)
+    | Instruction::Exit)
+}
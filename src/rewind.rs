@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+
+pub const DEFAULT_REWIND_FRAMES: usize = 600; //10 seconds at 60 fps
+
+// ring buffer of save states, only the newest one is kept in full
+// every older state is stored as the difference to the state recorded after it,
+// so stepping backwards always applies the newest delta to the current state
+pub struct Rewind {
+  capacity: usize,
+  latest: Option<Vec<u8>>,
+  deltas: VecDeque<Vec<u8>>,
+}
+
+impl Rewind {
+  pub fn new(capacity: usize) -> Rewind {
+    Rewind {
+      capacity,
+      latest: None,
+      deltas: VecDeque::with_capacity(capacity),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.deltas.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.deltas.is_empty()
+  }
+
+  pub fn clear(&mut self) {
+    self.latest = None;
+    self.deltas.clear();
+  }
+
+  pub fn record(&mut self, state: Vec<u8>) {
+    if let Some(latest) = self.latest.take() {
+      self.deltas.push_back(encode_delta(&state, &latest));
+
+      if self.deltas.len() > self.capacity {
+        self.deltas.pop_front();
+      }
+    }
+
+    self.latest = Some(state);
+  }
+
+  // steps one recorded frame back and returns the state to load
+  pub fn rewind(&mut self) -> Option<&[u8]> {
+    let delta = self.deltas.pop_back()?;
+    let latest = self.latest.as_mut()?;
+    apply_delta(latest, &delta);
+
+    Some(latest)
+  }
+}
+
+// the delta is the xor of both states, run length encoded as
+// target length followed by pairs of (unchanged bytes, changed bytes, changed data)
+fn encode_delta(from: &[u8], to: &[u8]) -> Vec<u8> {
+  let length = from.len().max(to.len());
+  let xor = |index: usize| from.get(index).copied().unwrap_or(0) ^ to.get(index).copied().unwrap_or(0);
+
+  let mut delta = Vec::new();
+  write_varint(&mut delta, to.len());
+
+  let mut index = 0;
+  while index < length {
+    let unchanged_start = index;
+    while index < length && xor(index) == 0 {
+      index += 1;
+    }
+
+    let changed_start = index;
+    while index < length && xor(index) != 0 {
+      index += 1;
+    }
+
+    write_varint(&mut delta, changed_start - unchanged_start);
+    write_varint(&mut delta, index - changed_start);
+    delta.extend((changed_start..index).map(xor));
+  }
+
+  delta
+}
+
+fn apply_delta(state: &mut Vec<u8>, delta: &[u8]) {
+  let mut position = 0;
+  let target_length = read_varint(delta, &mut position);
+
+  if state.len() < target_length {
+    state.resize(target_length, 0);
+  }
+
+  let mut index = 0;
+  while position < delta.len() {
+    index += read_varint(delta, &mut position);
+    let changed = read_varint(delta, &mut position);
+
+    for byte in delta[position..position + changed].iter() {
+      state[index] ^= byte;
+      index += 1;
+    }
+    position += changed;
+  }
+
+  state.truncate(target_length);
+}
+
+fn write_varint(data: &mut Vec<u8>, mut value: usize) {
+  while value >= 0x80 {
+    data.push((value as u8 & 0x7F) | 0x80);
+    value >>= 7;
+  }
+  data.push(value as u8);
+}
+
+fn read_varint(data: &[u8], position: &mut usize) -> usize {
+  let mut value = 0;
+  let mut shift = 0;
+
+  loop {
+    let byte = data[*position];
+    *position += 1;
+    value |= ((byte & 0x7F) as usize) << shift;
+    shift += 7;
+
+    if byte & 0x80 == 0 {
+      return value;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn round_trip(from: &[u8], to: &[u8]) {
+    let delta = encode_delta(from, to);
+    let mut state = from.to_vec();
+    apply_delta(&mut state, &delta);
+    assert_eq!(state, to);
+  }
+
+  #[test]
+  fn delta_round_trip() {
+    round_trip(&[], &[]);
+    round_trip(&[1, 2, 3], &[1, 2, 3]);
+    round_trip(&[1, 2, 3, 4, 5], &[1, 9, 3, 0, 5]);
+    round_trip(&[0; 8], &[7; 8]);
+    round_trip(&[1, 2, 3, 4, 5, 6], &[1, 2]); //truncated
+    round_trip(&[1, 2], &[1, 2, 0, 0, 5, 6]); //resized
+    round_trip(&[], &[1, 2, 3]);
+    round_trip(&[1, 2, 3], &[]);
+  }
+
+  #[test]
+  fn delta_round_trip_with_long_runs() {
+    let from: Vec<u8> = (0..1000).map(|index| index as u8).collect();
+    let mut to = from.clone();
+    to[0] ^= 1;
+    for byte in to[300..600].iter_mut() {
+      *byte = 0xFF;
+    }
+    to.extend_from_slice(&[3; 200]);
+
+    round_trip(&from, &to);
+    round_trip(&to, &from);
+  }
+
+  #[test]
+  fn rewinds_in_reverse_order_until_capacity() {
+    let mut rewind = Rewind::new(3);
+    for frame in 0..6u8 {
+      rewind.record(vec![frame; frame as usize + 1]);
+    }
+    assert_eq!(rewind.len(), 3);
+
+    assert_eq!(rewind.rewind(), Some(&[4; 5][..]));
+    assert_eq!(rewind.rewind(), Some(&[3; 4][..]));
+    assert_eq!(rewind.rewind(), Some(&[2; 3][..]));
+    assert_eq!(rewind.rewind(), None);
+    assert!(rewind.is_empty());
+  }
+
+  #[test]
+  fn records_after_a_rewind() {
+    let mut rewind = Rewind::new(10);
+    for frame in 0..4u8 {
+      rewind.record(vec![frame, frame]);
+    }
+
+    assert_eq!(rewind.rewind(), Some(&[2, 2][..]));
+    assert_eq!(rewind.rewind(), Some(&[1, 1][..]));
+
+    rewind.record(vec![7, 7, 7]);
+    rewind.record(vec![8]);
+
+    assert_eq!(rewind.rewind(), Some(&[7, 7, 7][..]));
+    assert_eq!(rewind.rewind(), Some(&[1, 1][..]));
+    assert_eq!(rewind.rewind(), Some(&[0, 0][..]));
+    assert_eq!(rewind.rewind(), None);
+  }
+
+  #[test]
+  fn clear_forgets_everything() {
+    let mut rewind = Rewind::new(10);
+    rewind.record(vec![1]);
+    rewind.record(vec![2]);
+    rewind.clear();
+
+    rewind.record(vec![3]);
+    assert_eq!(rewind.rewind(), None);
+  }
+}
@@ -0,0 +1,16 @@
+use std::fs;
+use std::io;
+
+pub struct RomLoader {
+  pub rom: Vec<u8>,
+}
+
+impl RomLoader {
+  pub fn load(file_name: &str) -> io::Result<RomLoader> {
+    let rom = fs::read(file_name)?;
+
+    Ok(RomLoader {
+      rom
+    })
+  }
+}
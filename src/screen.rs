@@ -0,0 +1,103 @@
+use crate::cpu::{SCHIP_HEIGHT, SCHIP_WIDTH};
+
+pub const PLANE_COUNT: usize = 2;
+pub const ALL_PLANES: u8 = 0b11;
+
+// framebuffer big enough for the largest resolution, only width x height pixels are in use
+// every pixel holds one bit per plane, plain chip-8 and super-chip only use the first plane
+#[derive(Clone)]
+pub struct Screen {
+  width: usize,
+  height: usize,
+  pixels: [[u8; SCHIP_WIDTH]; SCHIP_HEIGHT],
+}
+
+impl Screen {
+  pub fn new(width: usize, height: usize) -> Screen {
+    Screen {
+      width,
+      height,
+      pixels: [[0; SCHIP_WIDTH]; SCHIP_HEIGHT],
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    self.width
+  }
+
+  pub fn height(&self) -> usize {
+    self.height
+  }
+
+  pub fn resize(&mut self, width: usize, height: usize) {
+    self.width = width;
+    self.height = height;
+    self.clear(ALL_PLANES);
+  }
+
+  pub fn clear(&mut self, planes: u8) {
+    for row in self.pixels.iter_mut() {
+      for pixel in row.iter_mut() {
+        *pixel &= !planes;
+      }
+    }
+  }
+
+  // palette index of the pixel, 0 is the background
+  pub fn pixel(&self, x: usize, y: usize) -> u8 {
+    self.pixels[y][x]
+  }
+
+  pub fn set_pixel(&mut self, x: usize, y: usize, value: u8) {
+    self.pixels[y][x] = value;
+  }
+
+  // xors the pixel on the given planes and returns true if any of them was erased
+  pub fn toggle(&mut self, x: usize, y: usize, planes: u8) -> bool {
+    let erased = self.pixels[y][x] & planes != 0;
+    self.pixels[y][x] ^= planes;
+    erased
+  }
+
+  pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+    let width = self.width;
+    self.pixels[..self.height].iter().map(move |row| &row[..width])
+  }
+
+  pub fn scroll_down(&mut self, lines: usize, planes: u8) {
+    for y in (0..self.height).rev() {
+      for x in 0..self.width {
+        let moved = if y >= lines { self.pixels[y - lines][x] } else { 0 };
+        self.pixels[y][x] = (self.pixels[y][x] & !planes) | (moved & planes);
+      }
+    }
+  }
+
+  pub fn scroll_up(&mut self, lines: usize, planes: u8) {
+    for y in 0..self.height {
+      for x in 0..self.width {
+        let moved = if y + lines < self.height { self.pixels[y + lines][x] } else { 0 };
+        self.pixels[y][x] = (self.pixels[y][x] & !planes) | (moved & planes);
+      }
+    }
+  }
+
+  pub fn scroll_right(&mut self, columns: usize, planes: u8) {
+    for row in self.pixels[..self.height].iter_mut() {
+      for x in (0..self.width).rev() {
+        let moved = if x >= columns { row[x - columns] } else { 0 };
+        row[x] = (row[x] & !planes) | (moved & planes);
+      }
+    }
+  }
+
+  pub fn scroll_left(&mut self, columns: usize, planes: u8) {
+    let width = self.width;
+    for row in self.pixels[..self.height].iter_mut() {
+      for x in 0..width {
+        let moved = if x + columns < width { row[x + columns] } else { 0 };
+        row[x] = (row[x] & !planes) | (moved & planes);
+      }
+    }
+  }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+// save state layout, all numbers are little endian:
+//   magic "CH8S", format version (u16), rom hash (u64)
+//   followed by sections of tag (4 bytes), length (u32) and payload
+// unknown sections are skipped so newer states stay readable as long as the version matches
+pub const STATE_MAGIC: &[u8; 4] = b"CH8S";
+pub const STATE_VERSION: u16 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StateError {
+  InvalidHeader,
+  UnsupportedVersion { version: u16 },
+  RomMismatch { expected: u64, found: u64 },
+  MachineMismatch,
+  MissingSection { tag: [u8; 4] },
+  Truncated,
+  InvalidData,
+}
+
+impl fmt::Display for StateError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      StateError::InvalidHeader => write!(f, "Not a save state"),
+      StateError::UnsupportedVersion { version } => write!(f, "Unsupported save state version {}", version),
+      StateError::RomMismatch { expected, found } => write!(f, "Save state belongs to ROM {:016X}, loaded ROM is {:016X}", found, expected),
+      StateError::MachineMismatch => write!(f, "Save state was made with a different instruction set"),
+      StateError::MissingSection { tag } => write!(f, "Save state is missing section {}", String::from_utf8_lossy(tag)),
+      StateError::Truncated => write!(f, "Save state is truncated"),
+      StateError::InvalidData => write!(f, "Save state contains invalid data"),
+    }
+  }
+}
+
+impl Error for StateError {}
+
+// 64 bit FNV-1a
+pub fn rom_hash(rom: &[u8]) -> u64 {
+  rom.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01B3))
+}
+
+pub struct StateWriter {
+  data: Vec<u8>,
+}
+
+impl StateWriter {
+  pub fn new(rom_hash: u64) -> StateWriter {
+    let mut data = Vec::new();
+    data.extend_from_slice(STATE_MAGIC);
+    data.extend_from_slice(&STATE_VERSION.to_le_bytes());
+    data.extend_from_slice(&rom_hash.to_le_bytes());
+
+    StateWriter { data }
+  }
+
+  pub fn section(&mut self, tag: &[u8; 4], payload: &[u8]) {
+    self.data.extend_from_slice(tag);
+    self.data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    self.data.extend_from_slice(payload);
+  }
+
+  pub fn finish(self) -> Vec<u8> {
+    self.data
+  }
+}
+
+pub struct StateReader<'a> {
+  sections: HashMap<[u8; 4], &'a [u8]>,
+}
+
+impl<'a> StateReader<'a> {
+  pub fn new(data: &'a [u8], rom_hash: u64) -> Result<StateReader<'a>, StateError> {
+    let mut payload = Payload::new(data);
+
+    if payload.bytes(4)? != STATE_MAGIC {
+      return Err(StateError::InvalidHeader);
+    }
+
+    let version = payload.u16()?;
+    if version != STATE_VERSION {
+      return Err(StateError::UnsupportedVersion { version });
+    }
+
+    let found = payload.u64()?;
+    if found != rom_hash {
+      return Err(StateError::RomMismatch { expected: rom_hash, found });
+    }
+
+    let mut sections = HashMap::new();
+    while !payload.is_empty() {
+      let mut tag = [0; 4];
+      tag.copy_from_slice(payload.bytes(4)?);
+      let length = payload.u32()? as usize;
+      sections.insert(tag, payload.bytes(length)?);
+    }
+
+    Ok(StateReader { sections })
+  }
+
+  pub fn section(&self, tag: &[u8; 4]) -> Result<Payload<'a>, StateError> {
+    self.sections.get(tag)
+      .map(|data| Payload::new(data))
+      .ok_or(StateError::MissingSection { tag: *tag })
+  }
+}
+
+// cursor over the bytes of a section
+pub struct Payload<'a> {
+  data: &'a [u8],
+}
+
+impl<'a> Payload<'a> {
+  pub fn new(data: &'a [u8]) -> Payload<'a> {
+    Payload { data }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], StateError> {
+    if self.data.len() < length {
+      return Err(StateError::Truncated);
+    }
+
+    let (bytes, rest) = self.data.split_at(length);
+    self.data = rest;
+    Ok(bytes)
+  }
+
+  pub fn u8(&mut self) -> Result<u8, StateError> {
+    Ok(self.bytes(1)?[0])
+  }
+
+  pub fn bool(&mut self) -> Result<bool, StateError> {
+    match self.u8()? {
+      0 => Ok(false),
+      1 => Ok(true),
+      _ => Err(StateError::InvalidData),
+    }
+  }
+
+  pub fn u16(&mut self) -> Result<u16, StateError> {
+    let mut bytes = [0; 2];
+    bytes.copy_from_slice(self.bytes(2)?);
+    Ok(u16::from_le_bytes(bytes))
+  }
+
+  pub fn u32(&mut self) -> Result<u32, StateError> {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(self.bytes(4)?);
+    Ok(u32::from_le_bytes(bytes))
+  }
+
+  pub fn u64(&mut self) -> Result<u64, StateError> {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(self.bytes(8)?);
+    Ok(u64::from_le_bytes(bytes))
+  }
+}
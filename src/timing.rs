@@ -0,0 +1,69 @@
+use crate::instruction::Instruction;
+
+// the vip runs its 1802 at 1.76 MHz, one machine cycle takes 8 clocks
+// a 60 Hz frame has 3668 machine cycles, the display dma steals 1024 of them
+// and the interrupt routine that decrements the timers another 46
+pub const VIP_CYCLES_PER_FRAME: u32 = 3668;
+const VIP_DISPLAY_CYCLES: u32 = 1024;
+const VIP_INTERRUPT_CYCLES: u32 = 46;
+pub const VIP_AVAILABLE_CYCLES: u32 = VIP_CYCLES_PER_FRAME - VIP_DISPLAY_CYCLES - VIP_INTERRUPT_CYCLES;
+
+const VIP_FETCH_CYCLES: u32 = 40; //fetch and dispatch in the interpreter loop
+const VIP_SKIP_CYCLES: u32 = 4; //extra cost when a skip is taken
+
+// with the vip model the cycle budget sets the speed, so instructions per frame are ignored
+// and instructions always run through the interpreter, the recompiler engine isn't used
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timing {
+  Simple, //a fixed number of instructions per frame
+  CosmacVip, //every instruction costs the machine cycles of the original interpreter routine
+}
+
+// machine cycles the vip interpreter spends on an instruction, including the fetch
+// skipped tells if the program counter moved anywhere but the next instruction
+// the numbers follow the disassembled interpreter and ignore data dependent branches inside the routines
+pub fn vip_cycles(instruction: &Instruction, skipped: bool) -> u32 {
+  let execute = match *instruction {
+    Instruction::ClearScreen => 24,
+    Instruction::Return => 10,
+    Instruction::Sys(_) => 10,
+    Instruction::Jump(_) => 12,
+    Instruction::Call(_) => 26,
+    Instruction::SkipEqualByte(_, _) | Instruction::SkipNotEqualByte(_, _) => 10,
+    Instruction::SkipEqual(_, _) | Instruction::SkipNotEqual(_, _) => 14,
+    Instruction::LoadByte(_, _) => 6,
+    Instruction::AddByte(_, _) => 10,
+    Instruction::Load(_, _)
+    | Instruction::Or(_, _)
+    | Instruction::And(_, _)
+    | Instruction::Xor(_, _)
+    | Instruction::Add(_, _)
+    | Instruction::Sub(_, _)
+    | Instruction::ShiftRight(_, _)
+    | Instruction::SubReverse(_, _)
+    | Instruction::ShiftLeft(_, _) => 44,
+    Instruction::LoadI(_) => 12,
+    Instruction::JumpOffset(_) => 22,
+    Instruction::Random(_, _) => 36,
+    Instruction::Draw(_, _, rows) => 26 + 45 * rows as u32,
+    Instruction::SkipKeyPressed(_) | Instruction::SkipKeyNotPressed(_) => 14,
+    Instruction::LoadDelayTimer(_) => 10,
+    Instruction::WaitForKey(_) => 19,
+    Instruction::SetDelayTimer(_) | Instruction::SetSoundTimer(_) => 10,
+    Instruction::AddI(_) => 16,
+    Instruction::LoadFont(_) => 20,
+    Instruction::StoreBcd(_) => 204,
+    Instruction::StoreRegisters(x) | Instruction::LoadRegisters(x) => 14 + 14 * (x as u32 + 1),
+    _ => 10, //not part of the vip interpreter
+  };
+
+  let skip = matches!(instruction,
+    Instruction::SkipEqualByte(_, _)
+    | Instruction::SkipNotEqualByte(_, _)
+    | Instruction::SkipEqual(_, _)
+    | Instruction::SkipNotEqual(_, _)
+    | Instruction::SkipKeyPressed(_)
+    | Instruction::SkipKeyNotPressed(_));
+
+  VIP_FETCH_CYCLES + execute + if skip && skipped { VIP_SKIP_CYCLES } else { 0 }
+}
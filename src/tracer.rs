@@ -0,0 +1,81 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+
+use crate::observer::{InstructionEvent, Observer, Registers};
+
+// writes one line per executed instruction with the registers before it ran, register it with Chip8::add_observer
+// every field has a fixed width and the mnemonic comes last, so traces of other emulators
+// can be compared with diff after cutting off the mnemonic:
+//   00000012 0200 00E0 V 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I 0000 DT 00 ST 00 SP 00 ; CLS
+pub struct Tracer {
+  writer: Box<dyn Write>,
+  address_range: Option<Range<usize>>,
+  cycle_window: Option<Range<u64>>,
+  cycle: u64,
+  error: Option<io::Error>,
+}
+
+impl Tracer {
+  pub fn new(writer: Box<dyn Write>) -> Tracer {
+    Tracer {
+      writer,
+      address_range: None,
+      cycle_window: None,
+      cycle: 0,
+      error: None,
+    }
+  }
+
+  pub fn create(path: &str) -> io::Result<Tracer> {
+    Ok(Tracer::new(Box::new(BufWriter::new(File::create(path)?))))
+  }
+
+  // only trace instructions at these addresses
+  pub fn set_address_range(&mut self, address_range: Option<Range<usize>>) {
+    self.address_range = address_range;
+  }
+
+  // only trace these cycles, the first executed instruction is cycle 0
+  pub fn set_cycle_window(&mut self, cycle_window: Option<Range<u64>>) {
+    self.cycle_window = cycle_window;
+  }
+
+  pub fn cycle(&self) -> u64 {
+    self.cycle
+  }
+
+  // flushes the trace and reports the first write error, writing stops after an error
+  pub fn finish(&mut self) -> io::Result<()> {
+    if let Some(error) = self.error.take() {
+      return Err(error);
+    }
+    self.writer.flush()
+  }
+
+  fn traced(&self, address: usize) -> bool {
+    self.address_range.as_ref().is_none_or(|range| range.contains(&address))
+      && self.cycle_window.as_ref().is_none_or(|window| window.contains(&self.cycle))
+  }
+
+  fn write_line(&mut self, event: &InstructionEvent, registers: &Registers) -> io::Result<()> {
+    write!(self.writer, "{:08} {:04X} {:04X} V", self.cycle, event.program_counter, event.opcode)?;
+    for value in registers.v.iter() {
+      write!(self.writer, " {:02X}", value)?;
+    }
+    writeln!(self.writer, " I {:04X} DT {:02X} ST {:02X} SP {:02X} ; {}",
+      registers.i, registers.delay_timer, registers.sound_timer, registers.stack_pointer, event.instruction)
+  }
+}
+
+impl Observer for Tracer {
+  fn before_instruction(&mut self, event: &InstructionEvent, registers: &Registers) {
+    if self.error.is_none() && self.traced(event.program_counter) {
+      if let Err(error) = self.write_line(event, registers) {
+        self.error = Some(error);
+      }
+    }
+
+    self.cycle += 1;
+  }
+}
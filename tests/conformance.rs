@@ -0,0 +1,122 @@
+// runs test roms headlessly and compares the final screen with the golden renders in tests/golden
+// roms/BC_test.ch8 always runs, community test roms (the timendus suite, corax+, ...) run when placed in roms/tests
+// set CHIP8_BLESS=1 to write missing or changed golden files instead of failing
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chip8::{Chip8, MachineModel, Quirks, RomLoader, Screen};
+
+const GOLDEN_DIRECTORY: &str = "tests/golden";
+const TEST_ROM_DIRECTORY: &str = "roms/tests";
+const FRAMES: usize = 300;
+const INSTRUCTIONS_PER_FRAME: usize = 100;
+
+// the test suites ask for the platform to test unless it is already in memory
+const PLATFORM_SELECTION_ADDRESS: usize = 0x01FF;
+
+struct Case {
+  rom: PathBuf,
+  model: Option<MachineModel>,
+  platform: Option<u8>,
+}
+
+impl Case {
+  // the rom name tells which machine a community test rom is meant for
+  fn for_rom(rom: PathBuf) -> Case {
+    let name = rom.file_stem().unwrap().to_string_lossy().to_lowercase();
+
+    let (model, platform) = if name.contains("xo") {
+      (Some(MachineModel::XoChip), Some(3))
+    } else if name.contains("schip") || name.contains("superchip") {
+      (Some(MachineModel::SuperChip11), Some(2))
+    } else if name.contains("quirks") {
+      (Some(MachineModel::CosmacVip), Some(1))
+    } else {
+      (None, None)
+    };
+
+    Case { rom, model, platform }
+  }
+
+  fn name(&self) -> String {
+    self.rom.file_stem().unwrap().to_string_lossy().into_owned()
+  }
+
+  fn run(&self) -> String {
+    let mut chip8 = match self.model {
+      Some(model) => Chip8::with_model(model).unwrap(),
+      None => Chip8::new(Quirks::default()),
+    };
+    chip8.set_seed(0);
+    chip8.load(RomLoader::load(self.rom.to_str().unwrap()).unwrap().rom).unwrap();
+
+    if let Some(platform) = self.platform {
+      chip8.memory_mut().write(PLATFORM_SELECTION_ADDRESS, platform).unwrap();
+    }
+
+    for frame in 0..FRAMES {
+      if let Err(error) = chip8.run_frame(INSTRUCTIONS_PER_FRAME) {
+        panic!("{} failed in frame {}: {}", self.name(), frame, error);
+      }
+      if chip8.has_exited() {
+        break;
+      }
+    }
+
+    render(chip8.screen_buffer())
+  }
+}
+
+// one character per pixel, . is off and the planes are 1 to 3 with # for the first plane alone
+fn render(screen: &Screen) -> String {
+  let mut text = format!("{}x{}\n", screen.width(), screen.height());
+  for row in screen.rows() {
+    text.extend(row.iter().map(|pixel| match pixel {
+      0 => '.',
+      1 => '#',
+      2 => '2',
+      _ => '3',
+    }));
+    text.push('\n');
+  }
+  text
+}
+
+fn cases() -> Vec<Case> {
+  let mut cases = vec![Case::for_rom(PathBuf::from("roms/BC_test.ch8"))];
+
+  if let Ok(entries) = fs::read_dir(TEST_ROM_DIRECTORY) {
+    let mut roms: Vec<PathBuf> = entries
+      .map(|entry| entry.unwrap().path())
+      .filter(|path| path.extension().is_some_and(|extension| extension == "ch8"))
+      .collect();
+    roms.sort();
+    cases.extend(roms.into_iter().map(Case::for_rom));
+  }
+
+  cases
+}
+
+#[test]
+fn test_roms_match_golden_screens() {
+  let bless = env::var("CHIP8_BLESS").is_ok_and(|value| value == "1");
+  let mut failures = Vec::new();
+
+  for case in cases() {
+    let screen = case.run();
+    let golden_path = Path::new(GOLDEN_DIRECTORY).join(format!("{}.txt", case.name()));
+
+    match fs::read_to_string(&golden_path) {
+      Ok(golden) if golden == screen => {}
+      _ if bless => {
+        fs::create_dir_all(GOLDEN_DIRECTORY).unwrap();
+        fs::write(&golden_path, &screen).unwrap();
+      }
+      Ok(golden) => failures.push(format!("{} differs from {}\nexpected:\n{}\nfound:\n{}", case.name(), golden_path.display(), golden, screen)),
+      Err(_) => failures.push(format!("{} has no golden screen, run with CHIP8_BLESS=1 to create {}\n{}", case.name(), golden_path.display(), screen)),
+    }
+  }
+
+  assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,23 @@
+use chip8::{Chip8, Engine, MachineModel};
+
+// i = 0x20E with a long load, store v0 there, load it back and loop
+const PROGRAM: [u16; 8] = [0xF000, 0x020E, 0xF055, 0xF000, 0x020E, 0xF065, 0x120C, 0x0000];
+
+fn coverage(engine: Engine, instruction_cache: bool) -> String {
+  let mut chip8 = Chip8::with_model(MachineModel::XoChip).unwrap();
+  chip8.set_engine(engine);
+  chip8.set_instruction_cache(instruction_cache);
+  chip8.memory_mut().enable_coverage();
+  chip8.load(PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  chip8.run_frame(20).unwrap();
+
+  let text = chip8.memory().coverage().unwrap().to_text(chip8.rom_range());
+  text.lines().filter(|line| !line.starts_with('#')).collect::<Vec<&str>>().join("\n")
+}
+
+#[test]
+fn long_instructions_are_fully_executed() {
+  for (engine, instruction_cache) in [(Engine::Interpreter, false), (Engine::Interpreter, true), (Engine::Recompiler, true)].iter() {
+    assert_eq!(coverage(*engine, *instruction_cache), "0200-020D X--\n020E-020E -RW", "{:?} {}", engine, instruction_cache);
+  }
+}
@@ -0,0 +1,123 @@
+// drives the interpreter one instruction at a time through small hand assembled programs
+use chip8::{Chip8, Chip8Error, MachineModel, Quirks};
+
+fn chip8(program: &[u16]) -> Chip8 {
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.load(program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  chip8
+}
+
+fn keys(down: &[usize]) -> [bool; 16] {
+  let mut keys = [false; 16];
+  for key in down {
+    keys[*key] = true;
+  }
+  keys
+}
+
+#[test]
+fn fx0a_completes_on_press_and_release_between_steps() {
+  let mut chip8 = chip8(&[0xF30A, 0x1202]);
+  chip8.step_instruction().unwrap();
+  assert!(chip8.is_waiting_for_key());
+
+  chip8.set_input(keys(&[0xA]));
+  chip8.set_input(keys(&[]));
+  chip8.step_instruction().unwrap();
+
+  assert!(!chip8.is_waiting_for_key());
+  assert_eq!(chip8.registers().v[3], 0xA);
+}
+
+#[test]
+fn scrolling_needs_superchip_11() {
+  let scrolled = |model| {
+    let mut chip8 = Chip8::with_model(model).unwrap();
+    chip8.load(vec![0xA0, 0x00, 0xD0, 0x15, 0x00, 0xC1]).unwrap();
+    for _ in 0..3 {
+      chip8.step_instruction().unwrap();
+    }
+    chip8.screen_buffer().pixel(0, 0) == 0
+  };
+
+  assert!(!scrolled(MachineModel::SuperChip10));
+  assert!(scrolled(MachineModel::SuperChip11));
+}
+
+#[test]
+fn fx55_and_fx65_include_vx() {
+  // v0 = 1, v1 = 2, v2 = 3, i = 0x300, store v0-v2, clear them, load v0-v1
+  let mut chip8 = chip8(&[0x6001, 0x6102, 0x6203, 0xA300, 0xF255, 0x6000, 0x6100, 0x6200, 0xF165]);
+  for _ in 0..9 {
+    chip8.step_instruction().unwrap();
+  }
+
+  assert_eq!(chip8.memory().as_slice()[0x300..0x304], [1, 2, 3, 0]);
+  assert_eq!(chip8.registers().v[..3], [1, 2, 0]);
+  assert_eq!(chip8.registers().i, 0x300);
+}
+
+fn run_until_error(chip8: &mut Chip8) -> Chip8Error {
+  for _ in 0..100 {
+    if let Err(error) = chip8.step_instruction() {
+      return error;
+    }
+  }
+  panic!("no error after 100 instructions");
+}
+
+#[test]
+fn return_without_call_underflows() {
+  let mut chip8 = chip8(&[0x6001, 0x00EE]);
+  assert_eq!(run_until_error(&mut chip8), Chip8Error::StackUnderflow { address: 0x202 });
+}
+
+#[test]
+fn running_off_the_end_of_memory() {
+  // the last word of memory holds 0000, which is ignored
+  let mut past_end = chip8(&[0x1FFE]);
+  assert_eq!(run_until_error(&mut past_end), Chip8Error::ProgramCounterOutOfRange { address: 0x1000 });
+
+  let mut last_byte = chip8(&[0x1FFF]);
+  assert_eq!(run_until_error(&mut last_byte), Chip8Error::ProgramCounterOutOfRange { address: 0xFFF });
+}
+
+#[test]
+fn key_skips_need_a_valid_key() {
+  let mut chip8 = chip8(&[0x650F, 0xE59E, 0x6510, 0xE5A1]);
+  assert_eq!(run_until_error(&mut chip8), Chip8Error::InvalidKey { key: 0x10 });
+  assert_eq!(chip8.registers().program_counter, 0x206);
+}
+
+#[test]
+fn unknown_opcodes_are_reported() {
+  let mut chip8 = chip8(&[0x6001, 0x800F]);
+  assert_eq!(run_until_error(&mut chip8), Chip8Error::UnknownOpcode { opcode: 0x800F, address: 0x202 });
+}
+
+#[test]
+fn calls_overflow_at_the_stack_depth() {
+  // a subroutine that calls itself
+  let mut chip8 = chip8(&[0x2200]);
+  chip8.set_stack_depth(4);
+
+  assert_eq!(run_until_error(&mut chip8), Chip8Error::StackOverflow { address: 0x200 });
+  assert_eq!(chip8.registers().stack_pointer, 4);
+}
+
+#[test]
+fn vip_stack_lives_in_memory() {
+  // 0x200 calls 0x206, which calls 0x20A, which returns
+  let mut chip8 = chip8(&[0x2206, 0x1202, 0x1204, 0x220A, 0x1208, 0x00EE]);
+  chip8.set_stack_in_memory(true);
+  for _ in 0..2 {
+    chip8.step_instruction().unwrap();
+  }
+
+  assert_eq!(chip8.memory().as_slice()[0xEA0..0xEA6], [0x02, 0x02, 0x02, 0x08, 0x00, 0x00]);
+
+  // the program changes the return address in memory, the return follows it
+  chip8.memory_mut().write(0xEA3, 0x04).unwrap();
+  chip8.step_instruction().unwrap();
+  assert_eq!(chip8.registers().program_counter, 0x204);
+}
@@ -0,0 +1,79 @@
+use std::env;
+use std::fs;
+
+use chip8::{Chip8, Chip8Error, Font, FontSet, MachineModel, Quirks};
+
+fn run(chip8: &mut Chip8, program: &[u16]) {
+  chip8.load(program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  for _ in 0..program.len() {
+    chip8.step_instruction().unwrap();
+  }
+}
+
+fn installed(chip8: &Chip8, font: &Font) -> bool {
+  let memory = chip8.memory().as_slice();
+  let small = chip8.font_address();
+  let big = small + font.small().len();
+  memory[small..big] == *font.small() && memory[big..big + font.big().len()] == *font.big()
+}
+
+#[test]
+fn digits_point_at_the_font_address() {
+  let digit = |opcode| {
+    let mut chip8 = Chip8::with_model(MachineModel::SuperChip11).unwrap();
+    chip8.set_font_address(0x100).unwrap();
+    run(&mut chip8, &[0x6A07, opcode]);
+    assert!(installed(&chip8, chip8.font()));
+    chip8.registers().i
+  };
+
+  assert_eq!(digit(0xFA29), 0x100 + 7 * 5);
+  assert_eq!(digit(0xFA30), 0x100 + 80 + 7 * 10);
+}
+
+#[test]
+fn custom_font_file_is_installed() {
+  let glyphs: Vec<u8> = (0..80).collect();
+  let path = env::temp_dir().join(format!("chip8-font-{}.bin", std::process::id()));
+  fs::write(&path, &glyphs).unwrap();
+  let font = Font::load(path.to_str().unwrap());
+  fs::remove_file(&path).unwrap();
+
+  let font = font.unwrap();
+  assert_eq!(font.small(), &glyphs[..]);
+
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.set_font(font.clone()).unwrap();
+  assert!(installed(&chip8, &font));
+
+  // loading a rom installs it again
+  chip8.load(vec![0x12, 0x00]).unwrap();
+  assert!(installed(&chip8, &font));
+}
+
+#[test]
+fn every_builtin_font_installs() {
+  for set in [FontSet::CosmacVip, FontSet::Dream6800, FontSet::Eti660, FontSet::Chip48].iter() {
+    let font = Font::builtin(*set);
+    let mut chip8 = Chip8::new(Quirks::default());
+    chip8.set_font(font.clone()).unwrap();
+
+    assert!(installed(&chip8, &font), "{:?}", set);
+    assert_eq!(chip8.font(), &font);
+  }
+}
+
+#[test]
+fn font_outside_memory_is_rejected() {
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.set_font_address(0x50).unwrap();
+
+  assert_eq!(chip8.set_font_address(0xFF0), Err(Chip8Error::MemoryOutOfRange { address: 0xFF0 + 240 }));
+  assert_eq!(chip8.font_address(), 0x50);
+  assert!(installed(&chip8, chip8.font()));
+  assert!(chip8.memory().as_slice()[0xF00..].iter().all(|byte| *byte == 0));
+
+  // the last address the whole font fits at
+  chip8.set_font_address(0x1000 - 240).unwrap();
+  assert!(installed(&chip8, chip8.font()));
+}
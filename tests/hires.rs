@@ -0,0 +1,53 @@
+use chip8::{Chip8, InstructionSet, Quirks};
+
+// the vip two-page interpreter jumps from 0x200 into its own code and starts the program at 0x2C0
+fn hires_rom(program: &[u16]) -> Vec<u8> {
+  let mut rom = vec![0; 0xC0];
+  rom[..2].copy_from_slice(&[0x12, 0x60]);
+  rom.extend(program.iter().flat_map(|opcode| opcode.to_be_bytes()));
+  rom
+}
+
+fn load(rom: Vec<u8>) -> Chip8 {
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.load(rom).unwrap();
+  chip8
+}
+
+#[test]
+fn rom_starting_with_1260_runs_in_hires() {
+  let mut chip8 = load(hires_rom(&[0x12C0]));
+
+  assert_eq!(chip8.instruction_set(), InstructionSet::HiresChip8);
+  assert_eq!((chip8.screen_buffer().width(), chip8.screen_buffer().height()), (64, 64));
+
+  chip8.step_instruction().unwrap();
+  assert_eq!(chip8.registers().program_counter, 0x2C0);
+}
+
+#[test]
+fn opcode_0230_clears_the_hires_screen() {
+  // draw a row at y = 40, clear and loop, the sprite is at 0x2CA
+  let mut chip8 = load(hires_rom(&[0xA2CA, 0x6128, 0xD011, 0x0230, 0x12C8, 0xFF00]));
+  for _ in 0..4 {
+    chip8.step_instruction().unwrap();
+  }
+  assert_eq!(chip8.screen_buffer().pixel(0, 40), 1);
+
+  chip8.step_instruction().unwrap();
+  assert_eq!(chip8.screen_buffer().pixel(0, 40), 0);
+}
+
+#[test]
+fn normal_rom_is_not_hires() {
+  // 0230 is an ignored machine code call outside of hires mode
+  let mut chip8 = load(vec![0xA2, 0x0A, 0x61, 0x10, 0xD0, 0x11, 0x02, 0x30, 0x12, 0x08, 0xFF, 0x00]);
+  for _ in 0..4 {
+    chip8.step_instruction().unwrap();
+  }
+
+  assert_eq!(chip8.instruction_set(), InstructionSet::Chip8);
+  assert_eq!((chip8.screen_buffer().width(), chip8.screen_buffer().height()), (64, 32));
+  assert_eq!(chip8.screen_buffer().pixel(0, 16), 1);
+  assert_eq!(chip8.registers().program_counter, 0x208);
+}
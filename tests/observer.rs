@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use chip8::{Access, Chip8, DrawEvent, InstructionEvent, Observer, Quirks, Registers, Timer};
+
+#[derive(Debug, PartialEq)]
+enum Event {
+  Before(usize, u16),
+  After(usize, u16, usize),
+  Draw(DrawEvent),
+  Clear,
+  TimerWrite(Timer, u8),
+  Sound(bool),
+}
+
+#[derive(Default)]
+struct Recorder {
+  events: Vec<Event>,
+}
+
+impl Observer for Recorder {
+  fn before_instruction(&mut self, event: &InstructionEvent, _registers: &Registers) {
+    self.events.push(Event::Before(event.program_counter, event.opcode));
+  }
+
+  fn after_instruction(&mut self, event: &InstructionEvent, _before: &Registers, after: &Registers) {
+    self.events.push(Event::After(event.program_counter, event.opcode, after.program_counter));
+  }
+
+  fn draw(&mut self, event: &DrawEvent) {
+    self.events.push(Event::Draw(*event));
+  }
+
+  fn clear_screen(&mut self, _planes: u8) {
+    self.events.push(Event::Clear);
+  }
+
+  fn timer_write(&mut self, timer: Timer, value: u8) {
+    self.events.push(Event::TimerWrite(timer, value));
+  }
+
+  fn sound(&mut self, playing: bool) {
+    self.events.push(Event::Sound(playing));
+  }
+}
+
+fn record(program: &[u16]) -> (Chip8, Rc<RefCell<Recorder>>) {
+  let recorder = Rc::new(RefCell::new(Recorder::default()));
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.load(program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  chip8.add_observer(Box::new(recorder.clone()));
+  (chip8, recorder)
+}
+
+fn take(recorder: &Rc<RefCell<Recorder>>, keep: fn(&Event) -> bool) -> Vec<Event> {
+  recorder.borrow_mut().events.drain(..).filter(keep).collect()
+}
+
+#[test]
+fn instructions_are_reported_before_and_after() {
+  let (mut chip8, recorder) = record(&[0x00E0, 0x1206, 0x0000, 0x6001]);
+  for _ in 0..3 {
+    chip8.step_instruction().unwrap();
+  }
+
+  assert_eq!(take(&recorder, |_| true), [
+    Event::Before(0x200, 0x00E0),
+    Event::Clear,
+    Event::After(0x200, 0x00E0, 0x202),
+    Event::Before(0x202, 0x1206),
+    Event::After(0x202, 0x1206, 0x206),
+    Event::Before(0x206, 0x6001),
+    Event::After(0x206, 0x6001, 0x208),
+  ]);
+}
+
+#[test]
+fn draws_report_the_sprite_area_and_collision() {
+  // an 8x3 sprite at (62, 30) drawn twice
+  let (mut chip8, recorder) = record(&[0x603E, 0x611E, 0xA20C, 0xD013, 0xD013, 0x120A, 0xFFFF, 0xFF00]);
+  for _ in 0..5 {
+    chip8.step_instruction().unwrap();
+  }
+
+  let draw = |collision| Event::Draw(DrawEvent { x: 62, y: 30, width: 8, height: 3, planes: 1, collision });
+  assert_eq!(take(&recorder, |event| matches!(event, Event::Draw(_))), [draw(false), draw(true)]);
+}
+
+#[test]
+fn sound_is_reported_on_changes_only() {
+  // sound timer = 2, then = 3 while playing
+  let (mut chip8, recorder) = record(&[0x6002, 0xF018, 0x6003, 0xF018, 0x1208]);
+  for _ in 0..4 {
+    chip8.step_instruction().unwrap();
+  }
+  for _ in 0..5 {
+    chip8.tick_timers();
+  }
+
+  let sound = take(&recorder, |event| matches!(event, Event::Sound(_) | Event::TimerWrite(..)));
+  assert_eq!(sound, [
+    Event::TimerWrite(Timer::Sound, 2),
+    Event::Sound(true),
+    Event::TimerWrite(Timer::Sound, 3),
+    Event::Sound(false),
+  ]);
+}
+
+#[test]
+fn watchpoints_see_register_stores() {
+  // v0 = 1, v1 = 2, store both at 0x300
+  let (mut chip8, _) = record(&[0x6001, 0x6102, 0xA300, 0xF155]);
+  chip8.memory_mut().add_watchpoint(0x301..0x310, Access::Write);
+  for _ in 0..4 {
+    chip8.step_instruction().unwrap();
+  }
+
+  let hits: Vec<(usize, u8)> = chip8.memory_mut().take_hits().iter().map(|hit| (hit.address, hit.value)).collect();
+  assert_eq!(hits, [(0x301, 2)]);
+}
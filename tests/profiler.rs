@@ -0,0 +1,82 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use chip8::{Chip8, Chip8Error, Profiler, Quirks};
+
+// calls 0x206 twice and then loops at 0x204
+const PROGRAM: [u16; 5] = [0x2206, 0x2206, 0x1204, 0x7001, 0x00EE];
+
+fn profile(program: &[u16], frames: usize) -> (Chip8, Rc<RefCell<Profiler>>) {
+  let profiler = Rc::new(RefCell::new(Profiler::new(60)));
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.load(program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  chip8.add_observer(Box::new(profiler.clone()));
+  for _ in 0..frames {
+    chip8.run_frame(10).unwrap();
+  }
+  (chip8, profiler)
+}
+
+#[test]
+fn counts_executions_per_address() {
+  let (_, profiler) = profile(&PROGRAM, 2);
+  let profiler = profiler.borrow();
+
+  assert_eq!((profiler.instructions(), profiler.frames()), (20, 2));
+  let counts: Vec<u64> = [0x200, 0x202, 0x204, 0x206, 0x208].iter().map(|address| profiler.executions(*address)).collect();
+  assert_eq!(counts, [1, 1, 14, 2, 2]);
+  assert_eq!(profiler.pattern_count("2NNN"), 2);
+  assert_eq!(profiler.pattern_count("1NNN"), 14);
+}
+
+#[test]
+fn pairs_calls_with_returns() {
+  let (_, profiler) = profile(&PROGRAM, 1);
+  let subroutine = profiler.borrow().subroutines()[&0x206];
+
+  assert_eq!((subroutine.calls, subroutine.returns), (2, 2));
+}
+
+#[test]
+fn failed_calls_are_not_counted() {
+  let profiler = Rc::new(RefCell::new(Profiler::new(60)));
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.set_stack_depth(2);
+  chip8.load(vec![0x22, 0x00]).unwrap();
+  chip8.add_observer(Box::new(profiler.clone()));
+
+  assert_eq!(chip8.run_frame(10).err(), Some(Chip8Error::StackOverflow { address: 0x200 }));
+  assert_eq!(profiler.borrow().subroutines()[&0x200].calls, 2);
+  assert_eq!(profiler.borrow().executions(0x200), 3);
+}
+
+#[test]
+fn text_lists_hot_spots_by_count() {
+  let (_, profiler) = profile(&PROGRAM, 2);
+  let report = profiler.borrow().report_text();
+
+  let hot_spots: Vec<&str> = report.lines()
+    .skip_while(|line| *line != "Hot spots:")
+    .skip(1)
+    .take_while(|line| !line.is_empty())
+    .map(|line| &line[2..6])
+    .collect();
+  assert_eq!(hot_spots, ["0204", "0206", "0208", "0200", "0202"]);
+  assert!(report.contains("  0206         2 calls         2 returns"));
+}
+
+#[test]
+fn json_report_is_valid() {
+  let empty: serde_json::Value = serde_json::from_str(&Profiler::new(60).report_json()).unwrap();
+  assert_eq!(empty["instructions"], 0);
+  assert_eq!(empty["addresses"], serde_json::json!([]));
+
+  let (_, profiler) = profile(&PROGRAM, 2);
+  let report: serde_json::Value = serde_json::from_str(&profiler.borrow().report_json()).unwrap();
+
+  assert_eq!(report["instructions"], 20);
+  assert_eq!(report["frames"], 2);
+  assert_eq!(report["opcodes"]["1NNN"], 14);
+  assert_eq!(report["addresses"][0], serde_json::json!({ "address": 0x204, "count": 14, "instruction": "JP 0x204" }));
+  assert_eq!(report["subroutines"], serde_json::json!([{ "address": 0x206, "calls": 2, "returns": 2 }]));
+}
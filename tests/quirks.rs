@@ -0,0 +1,102 @@
+// runs the same program with a quirk switched on and off
+use chip8::{Chip8, Quirks};
+
+fn run(quirks: Quirks, program: &[u16], steps: usize) -> Chip8 {
+  let mut chip8 = Chip8::new(quirks);
+  chip8.load(program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  for _ in 0..steps {
+    chip8.step_instruction().unwrap();
+  }
+  chip8
+}
+
+// every quirk switched off
+fn none() -> Quirks {
+  Quirks {
+    shift_uses_vy: false,
+    load_store_increments_i: false,
+    jump_uses_vx: false,
+    vf_reset: false,
+    clip_sprites: false,
+    display_wait: false,
+    i_overflow_flag: false,
+  }
+}
+
+fn both(set: fn(&mut Quirks), program: &[u16], steps: usize) -> (Chip8, Chip8) {
+  let mut on = none();
+  set(&mut on);
+  (run(on, program, steps), run(none(), program, steps))
+}
+
+#[test]
+fn vf_reset() {
+  for operation in [0x8011, 0x8012, 0x8013].iter() {
+    let (on, off) = both(|quirks| quirks.vf_reset = true, &[0x6F05, 0x6003, 0x6106, *operation], 4);
+    assert_eq!(on.registers().v[0xF], 0, "{:04X}", operation);
+    assert_eq!(off.registers().v[0xF], 5, "{:04X}", operation);
+    assert_eq!(on.registers().v[0], off.registers().v[0]);
+  }
+}
+
+#[test]
+fn load_store_increments_i() {
+  for operation in [0xF255, 0xF265].iter() {
+    let (on, off) = both(|quirks| quirks.load_store_increments_i = true, &[0xA300, *operation], 2);
+    assert_eq!(on.registers().i, 0x303, "{:04X}", operation);
+    assert_eq!(off.registers().i, 0x300, "{:04X}", operation);
+  }
+}
+
+#[test]
+fn shift_uses_vy() {
+  // v0 = 0x03, v1 = 0x10
+  let (on, off) = both(|quirks| quirks.shift_uses_vy = true, &[0x6003, 0x6110, 0x8016], 3);
+  assert_eq!((on.registers().v[0], on.registers().v[0xF]), (0x08, 0));
+  assert_eq!((off.registers().v[0], off.registers().v[0xF]), (0x01, 1));
+
+  let (on, off) = both(|quirks| quirks.shift_uses_vy = true, &[0x6003, 0x6190, 0x801E], 3);
+  assert_eq!((on.registers().v[0], on.registers().v[0xF]), (0x20, 1));
+  assert_eq!((off.registers().v[0], off.registers().v[0xF]), (0x06, 0));
+}
+
+#[test]
+fn jump_uses_vx() {
+  // v0 = 3, v2 = 5, jump to 0x210 plus one of them
+  let (on, off) = both(|quirks| quirks.jump_uses_vx = true, &[0x6003, 0x6205, 0xB210], 3);
+  assert_eq!(on.registers().program_counter, 0x215);
+  assert_eq!(off.registers().program_counter, 0x213);
+}
+
+#[test]
+fn clip_sprites() {
+  // two rows of 8 pixels at (60, 31) with the sprite at 0x20C
+  let program = [0x603C, 0x611F, 0xA20C, 0xD012, 0x1208, 0x0000, 0xFFFF];
+  let (on, off) = both(|quirks| quirks.clip_sprites = true, &program, 4);
+
+  for chip8 in [&on, &off].iter() {
+    assert_eq!(chip8.screen_buffer().pixel(63, 31), 1);
+  }
+  assert_eq!((on.screen_buffer().pixel(0, 31), on.screen_buffer().pixel(60, 0)), (0, 0));
+  assert_eq!((off.screen_buffer().pixel(0, 31), off.screen_buffer().pixel(60, 0)), (1, 1));
+  assert_eq!(off.screen_buffer().pixel(0, 0), 1);
+}
+
+#[test]
+fn display_wait() {
+  let (mut on, off) = both(|quirks| quirks.display_wait = true, &[0xD001, 0x6001], 2);
+  assert_eq!(on.registers().v[0], 0);
+  assert_eq!(off.registers().v[0], 1);
+
+  // the next timer tick ends the wait
+  on.tick_timers();
+  on.step_instruction().unwrap();
+  assert_eq!(on.registers().v[0], 1);
+}
+
+#[test]
+fn i_overflow_flag() {
+  let (on, off) = both(|quirks| quirks.i_overflow_flag = true, &[0xAFFF, 0x6001, 0xF01E], 3);
+  assert_eq!((on.registers().i, on.registers().v[0xF]), (0x1000, 1));
+  assert_eq!((off.registers().i, off.registers().v[0xF]), (0x1000, 0));
+}
@@ -0,0 +1,51 @@
+use chip8::{Chip8, Quirks};
+
+// v0 = random, then loop
+const PROGRAM: [u8; 4] = [0xC0, 0xFF, 0x12, 0x00];
+
+fn machine(seed: u64) -> Chip8 {
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.set_seed(seed);
+  chip8.load(PROGRAM.to_vec()).unwrap();
+  chip8
+}
+
+fn sequence(chip8: &mut Chip8, length: usize) -> Vec<u8> {
+  (0..length).map(|_| {
+    chip8.step_instruction().unwrap();
+    chip8.step_instruction().unwrap();
+    chip8.registers().v[0]
+  }).collect()
+}
+
+#[test]
+fn same_seed_gives_same_sequence() {
+  assert_eq!(sequence(&mut machine(42), 64), sequence(&mut machine(42), 64));
+  assert_eq!(machine(42).seed(), 42);
+}
+
+#[test]
+fn different_seeds_give_different_sequences() {
+  assert_ne!(sequence(&mut machine(1), 64), sequence(&mut machine(2), 64));
+}
+
+#[test]
+fn reseeding_restarts_the_sequence() {
+  let mut chip8 = machine(7);
+  let first = sequence(&mut chip8, 16);
+  chip8.set_seed(7);
+  assert_eq!(sequence(&mut chip8, 16), first);
+}
+
+#[test]
+fn restored_state_continues_the_sequence() {
+  let mut chip8 = machine(1234);
+  sequence(&mut chip8, 10);
+  let state = chip8.save_state();
+  let expected = sequence(&mut chip8, 32);
+
+  let mut restored = machine(99);
+  restored.load_state(&state).unwrap();
+  assert_eq!(restored.seed(), 1234);
+  assert_eq!(sequence(&mut restored, 32), expected);
+}
@@ -0,0 +1,89 @@
+// runs the recompiler in lockstep with the interpreter and compares the whole machine after every frame
+use std::fs;
+use std::path::PathBuf;
+
+use chip8::{Chip8, Engine, Quirks, RomLoader};
+
+const FRAMES: usize = 300;
+const INSTRUCTIONS_PER_FRAME: usize = 37; //odd so frames end in the middle of blocks
+
+fn machine(rom: &[u8], quirks: Quirks, engine: Engine) -> Chip8 {
+  let mut chip8 = Chip8::new(quirks);
+  chip8.set_seed(0x5EED);
+  chip8.set_engine(engine);
+  chip8.load(rom.to_vec()).unwrap();
+  chip8
+}
+
+// presses a different key every few frames so games leave their title screens
+fn keys(frame: usize) -> [bool; 16] {
+  let mut keys = [false; 16];
+  if frame % 8 < 4 {
+    keys[(frame / 8 * 7) % 16] = true;
+  }
+  keys
+}
+
+fn assert_lockstep(rom: &[u8], quirks: Quirks, name: &str) {
+  let mut interpreter = machine(rom, quirks, Engine::Interpreter);
+  let mut recompiler = machine(rom, quirks, Engine::Recompiler);
+
+  for frame in 0..FRAMES {
+    interpreter.set_input(keys(frame));
+    recompiler.set_input(keys(frame));
+
+    let expected = interpreter.run_frame(INSTRUCTIONS_PER_FRAME).map(|_| ());
+    let found = recompiler.run_frame(INSTRUCTIONS_PER_FRAME).map(|_| ());
+    assert_eq!(expected, found, "{} frame {}", name, frame);
+    assert!(interpreter.save_state() == recompiler.save_state(), "{} diverged in frame {}", name, frame);
+
+    if expected.is_err() {
+      break;
+    }
+  }
+}
+
+fn roms(directory: &str) -> Vec<PathBuf> {
+  let mut roms: Vec<PathBuf> = fs::read_dir(directory).unwrap()
+    .map(|entry| entry.unwrap().path())
+    .filter(|path| path.extension().is_some_and(|extension| extension == "ch8"))
+    .collect();
+  roms.sort();
+  roms
+}
+
+#[test]
+fn recompiler_matches_interpreter() {
+  for directory in ["roms/demos", "roms/games", "roms/programs", "roms/hires"].iter() {
+    for path in roms(directory) {
+      let rom = RomLoader::load(path.to_str().unwrap()).unwrap().rom;
+      let name = path.display().to_string();
+
+      assert_lockstep(&rom, Quirks::default(), &name);
+      assert_lockstep(&rom, Quirks::cosmac_vip(), &name);
+    }
+  }
+}
+
+#[test]
+fn recompiler_sees_self_modifying_code() {
+  let rom = [
+    0xA2, 0x0A, //LD I, 0x20A
+    0x60, 0x61, //LD V0, 0x61
+    0x61, 0x55, //LD V1, 0x55
+    0xF1, 0x55, //LD [I], V1 - turns the instruction at 0x20A into LD V1, 0x55
+    0x62, 0x00, //LD V2, 0x00
+    0x61, 0x11, //LD V1, 0x11
+    0xA3, 0x00, //LD I, 0x300
+    0xF1, 0x55, //LD [I], V1
+    0x12, 0x10, //JP 0x210
+  ];
+
+  for engine in [Engine::Interpreter, Engine::Recompiler].iter() {
+    let mut chip8 = machine(&rom, Quirks::default(), *engine);
+    chip8.run_frame(20).unwrap();
+    assert_eq!(chip8.memory().peek(0x301), Some(0x55), "{:?}", engine);
+  }
+
+  assert_lockstep(&rom, Quirks::default(), "self modifying code");
+}
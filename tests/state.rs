@@ -0,0 +1,111 @@
+use chip8::{Chip8, InstructionSet, Quirks, StateError};
+
+// draws a digit, calls a subroutine and then loops so the state has a screen, a stack and timers
+const PROGRAM: [u16; 8] = [0x6A05, 0xFA29, 0xD005, 0xFA15, 0x220C, 0x120A, 0x6B07, 0x00EE];
+
+const HEADER_SIZE: usize = 14;
+
+fn chip8() -> Chip8 {
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.set_seed(0);
+  chip8.load(PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  for _ in 0..PROGRAM.len() {
+    chip8.step_instruction().unwrap();
+  }
+  chip8
+}
+
+// tag and payload range of every section
+fn sections(data: &[u8]) -> Vec<([u8; 4], usize, usize)> {
+  let mut sections = Vec::new();
+  let mut offset = HEADER_SIZE;
+  while offset < data.len() {
+    let mut tag = [0; 4];
+    tag.copy_from_slice(&data[offset..offset + 4]);
+    let length = u32::from_le_bytes([data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7]]) as usize;
+    sections.push((tag, offset + 8, offset + 8 + length));
+    offset += 8 + length;
+  }
+  sections
+}
+
+fn payload_offset(data: &[u8], tag: &[u8; 4]) -> usize {
+  sections(data).iter().find(|section| &section.0 == tag).unwrap().1
+}
+
+#[test]
+fn save_load_save_is_identical() {
+  let state = chip8().save_state();
+
+  let mut loaded = Chip8::new(Quirks::default());
+  loaded.load(PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  loaded.load_state(&state).unwrap();
+
+  assert_eq!(loaded.save_state(), state);
+  assert_eq!(loaded.registers(), chip8().registers());
+}
+
+#[test]
+fn truncated_states_are_rejected() {
+  let state = chip8().save_state();
+  let boundaries: Vec<usize> = sections(&state).iter().map(|section| section.2).collect();
+
+  for length in 0..state.len() {
+    let result = chip8().load_state(&state[..length]);
+    if length == HEADER_SIZE || boundaries.contains(&length) {
+      // cut between sections, the state just lacks the rest of them
+      assert!(matches!(result, Err(StateError::MissingSection { .. })), "length {}: {:?}", length, result);
+    } else {
+      assert_eq!(result, Err(StateError::Truncated), "length {}", length);
+    }
+  }
+}
+
+#[test]
+fn state_of_another_rom_is_rejected() {
+  let state = chip8().save_state();
+
+  let mut other = Chip8::new(Quirks::default());
+  other.load(vec![0x12, 0x00]).unwrap();
+
+  assert!(matches!(other.load_state(&state), Err(StateError::RomMismatch { .. })));
+}
+
+#[test]
+fn state_of_another_instruction_set_is_rejected() {
+  let state = chip8().save_state();
+
+  let mut other = chip8();
+  other.set_instruction_set(InstructionSet::XoChip);
+
+  assert_eq!(other.load_state(&state), Err(StateError::MachineMismatch));
+}
+
+#[test]
+fn invalid_section_contents_are_rejected() {
+  let state = chip8().save_state();
+  let cpu = payload_offset(&state, b"CPU ");
+  let screen = payload_offset(&state, b"SCRN");
+  let keys = payload_offset(&state, b"KEYS");
+
+  let patches: [(usize, &[u8]); 9] = [
+    (cpu, &[9]), //instruction set
+    (cpu + 25, &[2]), //wait for input flag
+    (cpu + 26, &[16]), //input register
+    (cpu + 29, &[4]), //planes
+    (screen, &[0, 0, 0, 0]), //0x0 screen
+    (screen, &[128, 0, 32, 0]), //128x32 screen
+    (screen + 4, &[4]), //first pixel
+    (screen + 4 + 64 * 32 - 1, &[0xFF]), //last pixel
+    (keys + 6, &[16]), //key waited for
+  ];
+
+  for (offset, bytes) in patches.iter() {
+    let mut patched = state.clone();
+    patched[*offset..*offset + bytes.len()].copy_from_slice(bytes);
+
+    let mut chip8 = chip8();
+    assert_eq!(chip8.load_state(&patched), Err(StateError::InvalidData), "patch at {}", offset);
+    assert_eq!(chip8.save_state(), state);
+  }
+}
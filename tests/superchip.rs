@@ -0,0 +1,74 @@
+use chip8::{Chip8, MachineModel};
+
+fn run(program: &[u16], steps: usize) -> Chip8 {
+  let mut chip8 = Chip8::with_model(MachineModel::SuperChip11).unwrap();
+  chip8.load(program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  for _ in 0..steps {
+    chip8.step_instruction().unwrap();
+  }
+  chip8
+}
+
+fn size(chip8: &Chip8) -> (usize, usize) {
+  (chip8.screen_buffer().width(), chip8.screen_buffer().height())
+}
+
+fn lit(chip8: &Chip8) -> Vec<(usize, usize)> {
+  let screen = chip8.screen_buffer();
+  (0..screen.height())
+    .flat_map(|y| (0..screen.width()).map(move |x| (x, y)))
+    .filter(|(x, y)| screen.pixel(*x, *y) != 0)
+    .collect()
+}
+
+#[test]
+fn switches_resolution() {
+  assert_eq!(size(&run(&[0x1200], 0)), (64, 32));
+  assert_eq!(size(&run(&[0x00FF, 0x1202], 1)), (128, 64));
+  assert_eq!(size(&run(&[0x00FF, 0x00FE, 0x1204], 2)), (64, 32));
+}
+
+#[test]
+fn draws_16x16_sprites_in_hires() {
+  // 32 bytes of 0xFF at 0x20A
+  let mut program = vec![0x00FF, 0xA20A, 0xD120, 0x1206, 0x0000];
+  program.extend_from_slice(&[0xFFFF; 16]);
+  let chip8 = run(&program, 3);
+
+  let expected: Vec<(usize, usize)> = (0..16).flat_map(|y| (0..16).map(move |x| (x, y))).collect();
+  assert_eq!(lit(&chip8), expected);
+  assert_eq!(chip8.registers().v[0xF], 0);
+}
+
+#[test]
+fn scrolls() {
+  // one pixel at (8, 8) in hires, the sprite is at 0x20C
+  let setup = [0x00FF, 0x6008, 0xA20C, 0xD001];
+  let scrolled = |scroll| {
+    let mut program = setup.to_vec();
+    program.extend_from_slice(&[scroll, 0x120A, 0x8000]);
+    lit(&run(&program, setup.len() + 1))
+  };
+
+  assert_eq!(scrolled(0x00FB), [(12, 8)]);
+  assert_eq!(scrolled(0x00FC), [(4, 8)]);
+  assert_eq!(scrolled(0x00C3), [(8, 11)]);
+}
+
+#[test]
+fn flag_registers_survive_clearing_the_registers() {
+  // v0 = 1, v1 = 2, v2 = 3, save v0-v2, clear, load v0-v1
+  let chip8 = run(&[0x6001, 0x6102, 0x6203, 0xF275, 0x6000, 0x6100, 0x6200, 0xF185], 8);
+
+  assert_eq!(chip8.registers().v[..3], [1, 2, 0]);
+}
+
+#[test]
+fn exit_stops_the_machine() {
+  let mut chip8 = run(&[0x00FD, 0x6001], 1);
+  assert!(chip8.has_exited());
+
+  chip8.step_instruction().unwrap();
+  assert_eq!(chip8.registers().v[0], 0);
+  assert_eq!(chip8.registers().program_counter, 0x200);
+}
@@ -0,0 +1,61 @@
+use chip8::{Chip8, Engine, Quirks, Timing};
+
+// add 1 to v0 (50 machine cycles with the fetch), then jump back (52 cycles)
+const LOOP: [u8; 4] = [0x70, 0x01, 0x12, 0x00];
+
+fn vip_chip8(rom: Vec<u8>) -> Chip8 {
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.set_timing(Timing::CosmacVip);
+  chip8.load(rom).unwrap();
+  chip8
+}
+
+#[test]
+fn frame_runs_until_its_cycles_are_used_up() {
+  let mut chip8 = vip_chip8(LOOP.to_vec());
+
+  // 2598 cycles are left for the interpreter: 25 loops take 2550, the next add ends at 2600
+  chip8.run_frame(0).unwrap();
+  assert_eq!(chip8.registers().v[0], 26);
+  assert_eq!(chip8.registers().program_counter, 0x202);
+}
+
+#[test]
+fn overrun_is_taken_from_the_next_frame() {
+  let mut chip8 = vip_chip8(LOOP.to_vec());
+  chip8.run_frame(0).unwrap();
+
+  // the frame starts 2 cycles late: jump to 54, 24 loops to 2502, add to 2552, jump to 2604
+  chip8.run_frame(0).unwrap();
+  assert_eq!(chip8.registers().v[0], 51);
+  assert_eq!(chip8.registers().program_counter, 0x200);
+}
+
+#[test]
+fn simple_timing_ignores_cycles() {
+  let mut chip8 = vip_chip8(LOOP.to_vec());
+  chip8.set_timing(Timing::Simple);
+
+  chip8.run_frame(100).unwrap();
+  assert_eq!(chip8.registers().v[0], 50);
+}
+
+#[test]
+fn taken_skips_cost_extra_cycles() {
+  // add (50 cycles), a taken skip over an unused word (54 cycles), jump back (52 cycles)
+  let mut chip8 = vip_chip8(vec![0x70, 0x01, 0x41, 0x01, 0x00, 0x00, 0x12, 0x00]);
+
+  // 16 loops take 2496 cycles, the add ends at 2546 and the skip at 2600
+  chip8.run_frame(0).unwrap();
+  assert_eq!(chip8.registers().v[0], 17);
+  assert_eq!(chip8.registers().program_counter, 0x206);
+}
+
+#[test]
+fn vip_timing_ignores_instruction_count_and_engine() {
+  let mut recompiled = vip_chip8(LOOP.to_vec());
+  recompiled.set_engine(Engine::Recompiler);
+  recompiled.run_frame(1000).unwrap();
+
+  assert_eq!(recompiled.registers().v[0], 26);
+}
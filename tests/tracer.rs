@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use chip8::{Chip8, Quirks, Tracer};
+
+// a writer the test can still read after handing it to the tracer
+#[derive(Clone, Default)]
+struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+  fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+    self.0.borrow_mut().extend_from_slice(data);
+    Ok(data.len())
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+// v0 = 0x12, i = 0x345, then a subroutine at 0x208 that calls itself until the stack holds 17 addresses
+const PROGRAM: [u16; 6] = [0x6012, 0xA345, 0x00E0, 0x2208, 0x7101, 0x2208];
+
+fn trace(configure: impl FnOnce(&mut Tracer), steps: usize) -> Vec<String> {
+  let buffer = SharedBuffer::default();
+  let mut tracer = Tracer::new(Box::new(buffer.clone()));
+  configure(&mut tracer);
+  let tracer = Rc::new(RefCell::new(tracer));
+
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.set_stack_depth(32);
+  chip8.load(PROGRAM.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  chip8.add_observer(Box::new(tracer.clone()));
+  for _ in 0..steps {
+    chip8.step_instruction().unwrap();
+  }
+  tracer.borrow_mut().finish().unwrap();
+
+  let text = String::from_utf8(buffer.0.borrow().clone()).unwrap();
+  text.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn lines_have_a_fixed_format() {
+  let lines = trace(|_| {}, 40);
+  assert_eq!(lines.len(), 40);
+
+  assert_eq!(lines[0], "00000000 0200 6012 V 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I 0000 DT 00 ST 00 SP 00 ; LD V0, 0x12");
+  assert_eq!(lines[2], "00000002 0204 00E0 V 12 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I 0345 DT 00 ST 00 SP 00 ; CLS");
+  assert_eq!(lines[3], "00000003 0206 2208 V 12 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I 0345 DT 00 ST 00 SP 00 ; CALL 0x208");
+  assert_eq!(lines[35], "00000035 020A 2208 V 12 10 00 00 00 00 00 00 00 00 00 00 00 00 00 00 I 0345 DT 00 ST 00 SP 10 ; CALL 0x208");
+
+  let length = lines[0].find(';').unwrap();
+  assert!(lines.iter().all(|line| line.find(';') == Some(length)));
+}
+
+#[test]
+fn address_range_limits_the_trace() {
+  let lines = trace(|tracer| tracer.set_address_range(Some(0x208..0x20A)), 10);
+
+  assert_eq!(lines.len(), 3);
+  assert!(lines.iter().all(|line| line[9..13] == *"0208"));
+  assert_eq!(lines[0][..8], *"00000004");
+}
+
+#[test]
+fn cycle_window_limits_the_trace() {
+  let lines = trace(|tracer| tracer.set_cycle_window(Some(2..5)), 10);
+
+  let cycles: Vec<&str> = lines.iter().map(|line| &line[..8]).collect();
+  assert_eq!(cycles, ["00000002", "00000003", "00000004"]);
+}
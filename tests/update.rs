@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use chip8::{Chip8, Quirks};
+
+fn chip8(timer_frequency: u32) -> Chip8 {
+  let mut chip8 = Chip8::new(Quirks::default());
+  chip8.set_timer_frequency(timer_frequency);
+  chip8.set_instructions_per_frame(4);
+  // count instructions in v0
+  chip8.load(vec![0x70, 0x01, 0x12, 0x00]).unwrap();
+  chip8
+}
+
+fn update(chip8: &mut Chip8, milliseconds: u64) -> u64 {
+  let before = chip8.frame_count();
+  chip8.update(Duration::from_millis(milliseconds)).unwrap();
+  chip8.frame_count() - before
+}
+
+#[test]
+fn elapsed_time_accumulates_across_calls() {
+  let mut chip8 = chip8(60);
+  assert_eq!(chip8.frame_duration(), Duration::from_nanos(16_666_666));
+
+  assert_eq!(update(&mut chip8, 10), 0);
+  assert_eq!(update(&mut chip8, 10), 1);
+  // 3.3 ms were left over
+  assert_eq!(update(&mut chip8, 14), 1);
+  assert_eq!(update(&mut chip8, 50), 3);
+  assert_eq!(chip8.registers().v[0], 5 * 2);
+}
+
+#[test]
+fn catching_up_is_limited_after_a_stall() {
+  let mut chip8 = chip8(60);
+
+  assert_eq!(update(&mut chip8, 2000), 5);
+  assert_eq!(update(&mut chip8, 0), 0);
+  assert_eq!(update(&mut chip8, 17), 1);
+}
+
+#[test]
+fn frames_follow_the_timer_frequency() {
+  let mut chip8 = chip8(50);
+  assert_eq!(chip8.frame_duration(), Duration::from_millis(20));
+
+  assert_eq!(update(&mut chip8, 19), 0);
+  assert_eq!(update(&mut chip8, 1), 1);
+  assert_eq!(update(&mut chip8, 100), 5);
+  assert_eq!(chip8.registers().v[0], 6 * 2);
+}
@@ -0,0 +1,81 @@
+use chip8::{Chip8, MachineModel};
+
+fn run(program: &[u16], steps: usize) -> Chip8 {
+  let mut chip8 = Chip8::with_model(MachineModel::XoChip).unwrap();
+  chip8.load(program.iter().flat_map(|opcode| opcode.to_be_bytes()).collect()).unwrap();
+  for _ in 0..steps {
+    chip8.step_instruction().unwrap();
+  }
+  chip8
+}
+
+#[test]
+fn skips_jump_over_long_load() {
+  // every skip is taken with v0 = 0 and v1 = 0, 9xy0 gets v1 = 1 first
+  let skips: [&[u16]; 4] = [&[0x3000], &[0x4001], &[0x5010], &[0x6101, 0x9010]];
+
+  for skip in skips.iter() {
+    let mut program = skip.to_vec();
+    program.extend_from_slice(&[0xF000, 0x1234, 0x6A01]);
+    let chip8 = run(&program, skip.len() + 1);
+
+    let registers = chip8.registers();
+    assert_eq!((registers.i, registers.v[0xA]), (0, 1), "{:04X?}", skip);
+    assert_eq!(registers.program_counter, 0x200 + program.len() * 2, "{:04X?}", skip);
+  }
+}
+
+#[test]
+fn long_load_sets_16_bit_i() {
+  let chip8 = run(&[0xF000, 0xBEEF, 0x6A01], 1);
+
+  assert_eq!(chip8.registers().i, 0xBEEF);
+  assert_eq!(chip8.registers().program_counter, 0x204);
+}
+
+#[test]
+fn draws_on_the_selected_planes() {
+  // sprite rows f0 and 0f at 0x20A
+  let plane = |select| {
+    let chip8 = run(&[select, 0xA20A, 0xD011, 0x1206, 0x0000, 0xF00F], 3);
+    (0..8).map(|x| chip8.screen_buffer().pixel(x, 0)).collect::<Vec<u8>>()
+  };
+
+  assert_eq!(plane(0xF101), [1, 1, 1, 1, 0, 0, 0, 0]);
+  assert_eq!(plane(0xF201), [2, 2, 2, 2, 0, 0, 0, 0]);
+  // both planes take one row each, the first plane gets the first one
+  assert_eq!(plane(0xF301), [1, 1, 1, 1, 2, 2, 2, 2]);
+  assert_eq!(plane(0xF001), [0; 8]);
+}
+
+#[test]
+fn ranges_with_x_above_y_run_in_reverse() {
+  let chip8 = run(&[0x6101, 0x6202, 0x6303, 0xA300, 0x5312], 5);
+  assert_eq!(chip8.memory().as_slice()[0x300..0x304], [3, 2, 1, 0]);
+  assert_eq!(chip8.registers().i, 0x300);
+
+  let chip8 = run(&[0x6107, 0x6208, 0x6309, 0xA300, 0x5132, 0x6100, 0x6200, 0x6300, 0x5313], 9);
+  assert_eq!(chip8.registers().v[1..4], [9, 8, 7]);
+
+  let chip8 = run(&[0x6107, 0x6208, 0x6309, 0xA300, 0x5132], 5);
+  assert_eq!(chip8.memory().as_slice()[0x300..0x303], [7, 8, 9]);
+}
+
+#[test]
+fn scroll_up() {
+  // a row of 8 pixels at y = 5 moves to y = 3
+  let chip8 = run(&[0xA20A, 0x6105, 0xD011, 0x00D2, 0x1208, 0xFF00], 4);
+
+  assert_eq!(chip8.screen_buffer().pixel(0, 3), 1);
+  assert_eq!(chip8.screen_buffer().pixel(0, 5), 0);
+}
+
+#[test]
+fn addresses_all_64k() {
+  // i = 0xFFF0, store v0 and v1 there, clear them and load them back, fx55 moves i on xo-chip
+  let chip8 = run(&[0xF000, 0xFFF0, 0x6042, 0x6143, 0xF155, 0x6000, 0x6100, 0xF000, 0xFFF0, 0xF165], 8);
+
+  assert_eq!(chip8.memory().len(), 0x10000);
+  assert_eq!(chip8.memory().as_slice()[0xFFF0..0xFFF2], [0x42, 0x43]);
+  assert_eq!(chip8.registers().v[..2], [0x42, 0x43]);
+}